@@ -116,7 +116,7 @@ DLLD_get_deploy_dll_exe(DLLD_deploy_dll_executable_location DLLD_objdump_executa
 
 function(DLLD_add_deploy target_name)
     cmake_parse_arguments(DLLD_add_deploy
-            "BUILD_MODE;INSTALL_MODE;ALL;VERBOSE;COPY_VC_REDIST"
+            "BUILD_MODE;INSTALL_MODE;ALL;VERBOSE;COPY_VC_REDIST;QT_PLUGINS"
             "INSTALL_DESTINATION"
             "IGNORE;OPTIONAL_DLLS;FLAGS"
             ${ARGN})
@@ -158,6 +158,10 @@ function(DLLD_add_deploy target_name)
         list(APPEND flags "--copy-vc-redist")
     endif ()
 
+    if(${DLLD_add_deploy_QT_PLUGINS})
+        list(APPEND flags "--qt-plugins")
+    endif ()
+
     cmake_path(GET CMAKE_C_COMPILER PARENT_PATH c_compiler_path)
     if(c_compiler_path)
         list(APPEND flags "\"--shallow-search-dir=${c_compiler_path}\"")
@@ -0,0 +1,214 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// The Windows version that the application is deployed for, which tells the dlls that come with the system
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetOs {
+    Win7,
+    #[value(name = "win10-22h2")]
+    Win10_22h2,
+    Win11,
+    Server2022,
+    Wine,
+}
+
+const WIN7: &str = include_str!("baselines/win7.txt");
+const WIN10_22H2: &str = include_str!("baselines/win10-22h2.txt");
+const WIN11: &str = include_str!("baselines/win11.txt");
+const SERVER2022: &str = include_str!("baselines/server2022.txt");
+const WINE: &str = include_str!("baselines/wine.txt");
+
+/// A dll that comes with the system, with its version and exports if they are recorded
+#[derive(Debug, Clone, Default)]
+pub struct SystemDll {
+    pub version: Option<String>,
+    pub exports: Option<BTreeSet<String>>,
+}
+
+/// The dlls that come with a clean installation of the system, by lower-case name
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    dlls: BTreeMap<String, SystemDll>,
+}
+
+impl Baseline {
+    /// Parse a baseline file, which has one dll per line, like `kernel32.dll 10.0.19041.1 CreateFileW,ReadFile`.
+    /// The version (`-` if unknown) and comma-separated exports are optional. Empty lines and lines starting with
+    /// `#` are skipped.
+    pub fn parse(content: &str) -> Baseline {
+        let mut dlls = BTreeMap::new();
+        for line in content.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let name = fields.next().unwrap().to_lowercase();
+            let version = fields.next().filter(|v| *v != "-").map(|v| v.to_string());
+            let exports = fields.next().map(|e| e.split(',').map(|s| s.to_string()).collect());
+            dlls.insert(name, SystemDll { version, exports });
+        }
+        return Baseline { dlls };
+    }
+
+    /// The baseline file of `parse`, with `header` as comments
+    pub fn to_file(&self, header: &str) -> String {
+        let mut content: String = header.lines().map(|line| format!("# {line}\n")).collect();
+        for (name, dll) in &self.dlls {
+            content.push_str(name);
+            if dll.version.is_some() || dll.exports.is_some() {
+                content.push(' ');
+                content.push_str(dll.version.as_deref().unwrap_or("-"));
+            }
+            if let Some(exports) = dll.exports.as_ref().filter(|e| !e.is_empty()) {
+                content.push(' ');
+                content.push_str(&exports.iter().cloned().collect::<Vec<String>>().join(","));
+            }
+            content.push('\n');
+        }
+        return content;
+    }
+
+    pub fn builtin(os: TargetOs) -> Baseline {
+        return Baseline::parse(match os {
+            TargetOs::Win7 => WIN7,
+            TargetOs::Win10_22h2 => WIN10_22H2,
+            TargetOs::Win11 => WIN11,
+            TargetOs::Server2022 => SERVER2022,
+            TargetOs::Wine => WINE,
+        });
+    }
+
+    pub fn load(file: &Path) -> Result<Baseline, String> {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read \"{}\": {e}", file.display()))?;
+        let baseline = Baseline::parse(&content);
+        if baseline.is_empty() {
+            return Err(format!("\"{}\" has no dll", file.display()));
+        }
+        return Ok(baseline);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        return self.dlls.contains_key(&name.to_lowercase());
+    }
+
+    /// Exports of a system dll, if they are recorded
+    pub fn exports(&self, name: &str) -> Option<&BTreeSet<String>> {
+        return self.dlls.get(&name.to_lowercase())?.exports.as_ref();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.dlls.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.dlls.is_empty();
+    }
+
+    pub fn insert(&mut self, name: &str, dll: SystemDll) {
+        let entry = self.dlls.entry(name.to_lowercase()).or_default();
+        if entry.version.is_none() {
+            entry.version = dll.version;
+        }
+        // A dll of System32 and SysWOW64 may export different symbols, the union is kept
+        if let Some(exports) = dll.exports {
+            entry.exports.get_or_insert_with(BTreeSet::new).extend(exports);
+        }
+    }
+
+    /// Whether the Universal CRT is part of the system
+    pub fn has_ucrt(&self) -> bool {
+        return self.contains("ucrtbase.dll");
+    }
+}
+
+/// The baseline given by `--system-baseline` or `--target-os`. Without them, dlls on the host are checked on
+/// Windows, and the baseline of Windows 10 is taken elsewhere.
+pub fn select(target_os: Option<TargetOs>, file: Option<&str>) -> Result<Option<Baseline>, String> {
+    if let Some(file) = file {
+        return Baseline::load(Path::new(file)).map(Some);
+    }
+    if let Some(os) = target_os {
+        return Ok(Some(Baseline::builtin(os)));
+    }
+    if cfg!(target_os = "windows") {
+        return Ok(None);
+    }
+    return Ok(Some(Baseline::builtin(TargetOs::Win10_22h2)));
+}
+
+/// Find `name` in `dir` case-insensitively, since mounted Windows images and Wine prefixes differ in case
+fn child_dir(dir: &Path, name: &str) -> Option<PathBuf> {
+    return std::fs::read_dir(dir).ok()?
+        .filter_map(|e| e.ok())
+        .find(|e| e.file_name().to_str().is_some_and(|n| n.eq_ignore_ascii_case(name)) && e.path().is_dir())
+        .map(|e| e.path());
+}
+
+/// System dirs in `dir`, which can be a Wine prefix, a Windows dir, or a system dir itself
+pub fn system_dirs(dir: &Path) -> Vec<PathBuf> {
+    let candidates = [
+        ["drive_c", "windows", "system32"], ["drive_c", "windows", "syswow64"],
+        ["windows", "system32", ""], ["windows", "syswow64", ""],
+        ["system32", "", ""], ["syswow64", "", ""],
+    ];
+    let mut dirs = Vec::new();
+    for candidate in candidates {
+        let mut found = Some(dir.to_path_buf());
+        for name in candidate.iter().filter(|n| !n.is_empty()) {
+            found = found.and_then(|d| child_dir(&d, name));
+        }
+        if let Some(found) = found {
+            dirs.push(found);
+        }
+    }
+    if dirs.is_empty() {
+        dirs.push(dir.to_path_buf());
+    }
+    return dirs;
+}
+
+/// Dlls in `dir`, sorted
+pub fn dlls_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dlls: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dll")))
+        .collect();
+    dlls.sort();
+    return dlls;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn builtin_baselines_load() {
+        for os in TargetOs::value_variants() {
+            let baseline = Baseline::builtin(*os);
+            assert!(baseline.contains("kernel32.dll"), "{os:?}");
+            assert!(baseline.contains("KERNEL32.DLL"), "{os:?}");
+        }
+    }
+
+    #[test]
+    fn windows_baselines_have_no_redistributable_runtime() {
+        for os in [TargetOs::Win7, TargetOs::Win10_22h2, TargetOs::Win11, TargetOs::Server2022] {
+            assert!(!Baseline::builtin(os).contains("msvcp140.dll"), "{os:?}");
+        }
+    }
+
+    #[test]
+    fn modern_baselines_differ() {
+        let win10 = Baseline::builtin(TargetOs::Win10_22h2);
+        let win11 = Baseline::builtin(TargetOs::Win11);
+        let server2022 = Baseline::builtin(TargetOs::Server2022);
+        assert!(!win10.contains("windowsudk.shellcommon.dll") && win11.contains("windowsudk.shellcommon.dll"));
+        assert!(win10.contains("mfplat.dll") && !server2022.contains("mfplat.dll"));
+        assert!([&win10, &win11, &server2022].iter().all(|b| b.has_ucrt()));
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::exit;
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum BuildType {
+    /// Deduce from the imports of target binary
+    Auto,
+    Debug,
+    Release,
+}
+
+/// Debug builds of the Microsoft C/C++ runtime
+const DEBUG_CRT_DLLS: [&str; 6] = [
+    "vcruntime140d.dll",
+    "vcruntime140_1d.dll",
+    "msvcp140d.dll",
+    "ucrtbased.dll",
+    "concrt140d.dll",
+    "vcomp140d.dll",
+];
+
+/// Whether `name` is a debug build of the C/C++ runtime
+pub fn is_debug_crt(name: &str) -> bool {
+    let name = name.to_lowercase();
+    if DEBUG_CRT_DLLS.contains(&name.as_str()) {
+        return true;
+    }
+    // Older runtimes, like msvcr120d.dll and msvcp120d.dll
+    return (name.starts_with("msvcr") || name.starts_with("msvcp")) && name.ends_with("d.dll")
+        && name.trim_start_matches("msvcr").trim_start_matches("msvcp").trim_end_matches("d.dll")
+        .chars().all(|c| c.is_ascii_digit());
+}
+
+/// Release builds of the Microsoft C/C++ runtime start with these names. Their debug builds end with `d.dll`
+const RELEASE_CRT_PREFIXES: [&str; 4] = ["vcruntime", "msvcp", "concrt", "vcomp"];
+
+/// Whether `name` is a release build of the Microsoft C/C++ runtime
+fn is_release_crt(name: &str) -> bool {
+    let name = name.to_lowercase();
+    if is_debug_crt(&name) {
+        return false;
+    }
+    if RELEASE_CRT_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+        return true;
+    }
+    // Older runtimes, like msvcr120.dll. msvcrt.dll belongs to the system and is used by release and debug builds
+    return name.starts_with("msvcr") && name != "msvcrt.dll";
+}
+
+/// Qt modules by dll name without `Qt5`/`Qt6` prefix, like `core` for Qt6Core.dll. Some of them end with d,
+/// like Qt6Quick3D.dll and Qt6VirtualKeyboard.dll, so a trailing d only means a debug build of a known module
+const QT_MODULES: [&str; 168] = [
+    "core", "gui", "widgets", "network", "sql", "xml", "test", "concurrent", "dbus", "opengl", "openglwidgets",
+    "openglextensions", "printsupport", "svg", "svgwidgets", "uitools", "help", "designer",
+    "designercomponents", "core5compat", "shadertools", "statemachine", "statemachineqml", "qml", "qmlmodels",
+    "qmlworkerscript", "qmlcore", "qmlcompiler", "qmldom", "qmllocalstorage", "qmlxmllistmodel", "qmlmeta",
+    "qmlnetwork", "quick", "quickwidgets", "quickcontrols2", "quickcontrols2impl", "quickcontrols2basic",
+    "quickcontrols2basicstyleimpl", "quickcontrols2fusion", "quickcontrols2fusionstyleimpl",
+    "quickcontrols2imagine", "quickcontrols2imaginestyleimpl", "quickcontrols2material",
+    "quickcontrols2materialstyleimpl", "quickcontrols2universal", "quickcontrols2universalstyleimpl",
+    "quickcontrols2windowsstyleimpl", "quickcontrols2fluentwinui3styleimpl", "quickdialogs2",
+    "quickdialogs2quickimpl", "quickdialogs2utils", "quicklayouts", "quicktemplates2", "quickparticles",
+    "quickshapes", "quicktest", "quicktimeline", "quicktimelineblendtrees", "quickeffects", "quickvectorimage",
+    "quick3d", "quick3dassetimport", "quick3dassetutils", "quick3deffects", "quick3dglslparser",
+    "quick3dhelpers", "quick3dhelpersimpl", "quick3diblbaker", "quick3dparticles", "quick3dparticleeffects",
+    "quick3druntimerender", "quick3drender", "quick3dutils", "quick3dphysics", "quick3dphysicshelpers",
+    "quick3dspatialaudio", "quick3dxr", "3dcore", "3dextras", "3dinput", "3dlogic", "3danimation", "3drender",
+    "3dquick", "3dquickextras", "3dquickinput", "3dquickrender", "3dquickanimation", "3dquickscene2d",
+    "3dquickscene3d", "multimedia", "multimediawidgets", "multimediaquick", "spatialaudio", "networkauth",
+    "websockets", "webchannel", "webchannelquick", "webengine", "webenginecore", "webenginewidgets",
+    "webenginequick", "webenginequickdelegatesqml", "webview", "webviewquick", "webkit", "webkitwidgets",
+    "charts", "chartsqml", "datavisualization", "datavisualizationqml", "graphs", "graphswidgets", "bluetooth",
+    "nfc", "positioning", "positioningquick", "location", "sensors", "sensorsquick", "serialport", "serialbus",
+    "remoteobjects", "remoteobjectsqml", "scxml", "scxmlqml", "texttospeech", "virtualkeyboard",
+    "virtualkeyboardsettings", "virtualkeyboardqml", "labsanimation", "labsfolderlistmodel", "labsqmlmodels",
+    "labssettings", "labssharedimage", "labswavefrontmesh", "labsplatform", "pdf", "pdfwidgets", "pdfquick",
+    "httpserver", "grpc", "grpcquick", "protobuf", "protobufquick", "protobufqtcoretypes", "protobufqtguitypes",
+    "protobufwellknowntypes", "coap", "mqtt", "opcua", "knx", "insighttracker", "axbase", "axcontainer",
+    "axserver", "xmlpatterns", "script", "scripttools", "winextras", "gamepad", "quickcontrols", "purchasing",
+    "bodymovin", "lottie", "languageserver", "jsonrpc",
+];
+
+/// Build type of a Qt module by its name, like Qt6Core.dll for release and Qt6Cored.dll for debug
+pub fn qt_build_type(name: &str) -> Option<BuildType> {
+    let name = name.to_lowercase();
+    let stem = name.strip_suffix(".dll")?;
+    let module = stem.strip_prefix("qt5").or_else(|| stem.strip_prefix("qt6"))?;
+    if module.is_empty() {
+        return None;
+    }
+    if QT_MODULES.contains(&module) {
+        return Some(BuildType::Release);
+    }
+    if module.strip_suffix('d').is_some_and(|release| QT_MODULES.contains(&release)) {
+        return Some(BuildType::Debug);
+    }
+    // Unknown modules can't be told apart from debug builds by name, take it as a release build
+    return Some(BuildType::Release);
+}
+
+/// Whether importing `name` makes a binary a debug build
+fn is_debug_import(name: &str) -> bool {
+    return is_debug_crt(name) || qt_build_type(name) == Some(BuildType::Debug);
+}
+
+/// Build type of binary `name` that imports `imports`, or None if it can't be told, for example
+/// a MinGW binary that doesn't link to Qt
+pub fn build_type_of(name: &str, imports: &[String]) -> Option<BuildType> {
+    if is_debug_import(name) || imports.iter().any(|i| is_debug_import(i)) {
+        return Some(BuildType::Debug);
+    }
+    if qt_build_type(name).is_some()
+        || imports.iter().any(|i| is_release_crt(i) || qt_build_type(i) == Some(BuildType::Release)) {
+        return Some(BuildType::Release);
+    }
+    return None;
+}
+
+/// Deduce build type from the dlls imported by a binary
+pub fn detect(imports: &[String]) -> BuildType {
+    if imports.iter().any(|name| is_debug_import(name)) {
+        return BuildType::Debug;
+    }
+    return BuildType::Release;
+}
+
+/// Warn about debug builds of the C/C++ runtime in `imports`, which are not deployed because they are not
+/// redistributable
+pub fn warn_debug_crts<'a>(imports: impl Iterator<Item = &'a String>) {
+    let debug_crts: BTreeSet<String> = imports.filter(|name| is_debug_crt(name)).map(|name| name.to_lowercase()).collect();
+    if debug_crts.is_empty() {
+        return;
+    }
+    eprintln!("Warning: debug C/C++ runtime {} is not deployed, the deployment only runs where Visual Studio is installed",
+              debug_crts.into_iter().collect::<Vec<String>>().join(", "));
+}
+
+/// Warn if debug and release binaries are mixed in the deployment, and exit if `deny`.
+/// `binaries` maps deployed binaries to their build type, `expected` is the build type of the target.
+pub fn check_mixed(expected: Option<BuildType>, binaries: &BTreeMap<String, BuildType>, deny: bool) {
+    let names = |build_type: BuildType| -> Vec<String> {
+        return binaries.iter()
+            .filter(|(_, b)| **b == build_type)
+            .map(|(file, _)| std::path::Path::new(file).file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+    };
+    let debug = names(BuildType::Debug);
+    let release = names(BuildType::Release);
+    let message = match expected {
+        Some(BuildType::Debug) if !release.is_empty() =>
+            format!("the target is a debug build, but these are release builds: {}", release.join(", ")),
+        Some(BuildType::Release) if !debug.is_empty() =>
+            format!("the target is a release build, but these are debug builds: {}", debug.join(", ")),
+        None if !debug.is_empty() && !release.is_empty() =>
+            format!("debug builds {} are mixed with release builds {}", debug.join(", "), release.join(", ")),
+        _ => return,
+    };
+    if deny {
+        eprintln!("Debug and release dlls are mixed, {message}");
+        exit(15);
+    }
+    eprintln!("Warning: debug and release dlls are mixed, they may crash when loaded together: {message}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qt_modules_ending_with_d() {
+        assert_eq!(qt_build_type("Qt6VirtualKeyboard.dll"), Some(BuildType::Release));
+        assert_eq!(qt_build_type("Qt6VirtualKeyboardd.dll"), Some(BuildType::Debug));
+        assert_eq!(qt_build_type("Qt5Gamepad.dll"), Some(BuildType::Release));
+        assert_eq!(qt_build_type("Qt5Gamepadd.dll"), Some(BuildType::Debug));
+        assert_eq!(qt_build_type("Qt6Quick3D.dll"), Some(BuildType::Release));
+        assert_eq!(qt_build_type("Qt6Quick3Dd.dll"), Some(BuildType::Debug));
+        assert_eq!(qt_build_type("Qt63DCore.dll"), Some(BuildType::Release));
+        assert_eq!(qt_build_type("Qt63DCored.dll"), Some(BuildType::Debug));
+    }
+
+    #[test]
+    fn qt_modules() {
+        assert_eq!(qt_build_type("Qt6Core.dll"), Some(BuildType::Release));
+        assert_eq!(qt_build_type("Qt6Cored.dll"), Some(BuildType::Debug));
+        assert_eq!(qt_build_type("Qt5Widgetsd.dll"), Some(BuildType::Debug));
+        assert_eq!(qt_build_type("Qt6Foo.dll"), Some(BuildType::Release));
+        assert_eq!(qt_build_type("Qt6.dll"), None);
+        assert_eq!(qt_build_type("zlib1.dll"), None);
+    }
+
+    #[test]
+    fn build_type_of_imports() {
+        let imports = vec!["qt6virtualkeyboard.dll".to_string(), "vcruntime140.dll".to_string()];
+        assert_eq!(build_type_of("app.exe", &imports), Some(BuildType::Release));
+        let imports = vec!["qt6cored.dll".to_string(), "vcruntime140d.dll".to_string()];
+        assert_eq!(build_type_of("app.exe", &imports), Some(BuildType::Debug));
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::lock::LockedDll;
+
+/// A package in the dependency graph of Conan
+#[derive(Debug, Clone)]
+pub struct ConanPackage {
+    pub name: String,
+    pub version: Option<String>,
+    pub bin_dirs: Vec<PathBuf>,
+    /// Folder of the package in the Conan cache, if known
+    pub folder: Option<PathBuf>,
+}
+
+impl ConanPackage {
+    /// Reference of the package, like `zlib/1.3.1`
+    pub fn reference(&self) -> String {
+        return match &self.version {
+            Some(version) => format!("{}/{version}", self.name),
+            None => self.name.clone(),
+        };
+    }
+}
+
+/// Load packages from a Conan generators folder. It can contain `conanbuildinfo.json` or `conanbuildinfo.txt`
+/// of Conan 1, or the `*-data.cmake` files of CMakeDeps and the `conanrunenv-*` scripts of VirtualRunEnv.
+/// For Conan 2, the configuration that matches `debug` is preferred.
+pub fn load(dir: &Path, debug: bool) -> Result<Vec<ConanPackage>, String> {
+    if !crate::can_be_dir(&dir) {
+        return Err(format!("\"{}\" is not a directory", dir.display()));
+    }
+    let json = dir.join("conanbuildinfo.json");
+    if crate::is_file(&json) {
+        return load_buildinfo_json(&json);
+    }
+    let txt = dir.join("conanbuildinfo.txt");
+    if crate::is_file(&txt) {
+        return load_buildinfo_txt(&txt);
+    }
+    let mut packages = load_cmake_deps(dir, debug);
+    add_run_env_dirs(&mut packages, load_run_env(dir, debug));
+    if packages.is_empty() {
+        return Err(format!("No conanbuildinfo, CMakeDeps or conanrunenv files found in \"{}\"", dir.display()));
+    }
+    return Ok(packages);
+}
+
+#[derive(Deserialize)]
+struct BuildInfoJson {
+    dependencies: Vec<BuildInfoDependency>,
+}
+
+#[derive(Deserialize)]
+struct BuildInfoDependency {
+    name: String,
+    version: Option<String>,
+    #[serde(default)]
+    bin_paths: Vec<String>,
+}
+
+fn load_buildinfo_json(file: &Path) -> Result<Vec<ConanPackage>, String> {
+    let content = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+    let info: BuildInfoJson = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse \"{}\": {e}", file.display()))?;
+    return Ok(info.dependencies.into_iter().map(|dep| ConanPackage {
+        name: dep.name,
+        version: dep.version,
+        bin_dirs: dep.bin_paths.into_iter().map(PathBuf::from).collect(),
+        folder: None,
+    }).collect());
+}
+
+/// Read sections `[bindirs_<name>]` of conanbuildinfo.txt
+fn load_buildinfo_txt(file: &Path) -> Result<Vec<ConanPackage>, String> {
+    let content = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+    let mut packages: BTreeMap<String, ConanPackage> = BTreeMap::new();
+    let mut section = String::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line[1..line.len() - 1].to_string();
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let Some(name) = section.strip_prefix("bindirs_") else {
+            continue;
+        };
+        packages.entry(name.to_string())
+            .or_insert_with(|| ConanPackage { name: name.to_string(), version: None, bin_dirs: Vec::new(), folder: None })
+            .bin_dirs.push(PathBuf::from(line));
+    }
+    return Ok(packages.into_values().collect());
+}
+
+/// Parse `set(<var> "<value>" ...)`, returns the variable and quoted values
+fn parse_cmake_set(line: &str) -> Option<(&str, Vec<&str>)> {
+    let args = line.trim().strip_prefix("set(")?.strip_suffix(')')?;
+    let (var, rest) = args.split_once(' ')?;
+    let values = rest.split('"').skip(1).step_by(2).collect();
+    return Some((var, values));
+}
+
+/// Variables of one configuration of a package in a CMakeDeps `*-data.cmake` file
+#[derive(Debug, PartialEq)]
+struct CmakeData {
+    name: String,
+    config: String,
+    package_folder: String,
+    bin_dirs: Vec<PathBuf>,
+}
+
+/// Read `set(<pkg>_PACKAGE_FOLDER_<CONFIG> ...)` and `set(<pkg>_BIN_DIRS_<CONFIG> ...)` of a CMakeDeps data file
+fn parse_cmake_data(content: &str) -> Option<CmakeData> {
+    let mut name_and_config = None;
+    let mut package_folder = String::new();
+    for line in content.lines() {
+        let Some((var, values)) = parse_cmake_set(line) else {
+            continue;
+        };
+        if let Some((name, config)) = var.split_once("_PACKAGE_FOLDER_") {
+            name_and_config = Some((name.to_string(), config.to_string()));
+            package_folder = values.first().map(|v| v.to_string()).unwrap_or_default();
+        }
+    }
+    let (name, config) = name_and_config?;
+
+    let folder_var = format!("${{{name}_PACKAGE_FOLDER_{config}}}");
+    let bin_dirs_var = format!("{name}_BIN_DIRS_{config}");
+    let bin_dirs = content.lines()
+        .filter_map(parse_cmake_set)
+        .filter(|(var, _)| *var == bin_dirs_var)
+        .flat_map(|(_, values)| values.into_iter().map(|v| PathBuf::from(v.replace(&folder_var, &package_folder))))
+        .collect();
+    return Some(CmakeData { name, config, package_folder, bin_dirs });
+}
+
+/// Load packages from the `*-data.cmake` files of CMakeDeps
+fn load_cmake_deps(dir: &Path, debug: bool) -> Vec<ConanPackage> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut data_files: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.to_str().is_some_and(|s| s.ends_with("-data.cmake")))
+        .collect();
+    data_files.sort();
+
+    // name -> (config, package)
+    let mut packages: BTreeMap<String, (String, ConanPackage)> = BTreeMap::new();
+    for file in data_files {
+        let Some(data) = std::fs::read_to_string(&file).ok().and_then(|content| parse_cmake_data(&content)) else {
+            continue;
+        };
+
+        let file_name = file.file_name().unwrap().to_str().unwrap();
+        let cmake_file_name = file_name.split(&format!("-{}-", data.config.to_lowercase())).next().unwrap_or(file_name);
+        let package = ConanPackage {
+            version: cmake_package_version(dir, cmake_file_name),
+            name: data.name.clone(),
+            bin_dirs: data.bin_dirs,
+            folder: Some(PathBuf::from(data.package_folder)),
+        };
+
+        let preferred = if debug { "DEBUG" } else { "RELEASE" };
+        match packages.get(&data.name) {
+            Some((existing_config, _)) if existing_config == preferred => {}
+            _ => {
+                packages.insert(data.name, (data.config, package));
+            }
+        }
+    }
+    return packages.into_values().map(|(_, package)| package).collect();
+}
+
+/// Read `set(PACKAGE_VERSION "...")` from the version file of CMakeDeps
+fn cmake_package_version(dir: &Path, cmake_file_name: &str) -> Option<String> {
+    for name in [format!("{cmake_file_name}-config-version.cmake"), format!("{cmake_file_name}ConfigVersion.cmake")] {
+        let Ok(content) = std::fs::read_to_string(dir.join(name)) else {
+            continue;
+        };
+        for line in content.lines() {
+            if let Some(("PACKAGE_VERSION", values)) = parse_cmake_set(line) {
+                return values.first().map(|v| v.to_string());
+            }
+        }
+    }
+    return None;
+}
+
+/// Read PATH entries of the `conanrunenv-<config>-<arch>.bat` or `.sh` scripts of VirtualRunEnv, which
+/// `conanrun.bat` calls. Every entry is the bin dir of a package.
+fn load_run_env(dir: &Path, debug: bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut scripts: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.file_name().and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("conanrunenv-") && (n.ends_with(".bat") || n.ends_with(".sh"))))
+        .collect();
+    scripts.sort();
+    let preferred = if debug { "-debug-" } else { "-release-" };
+    let Some(script) = scripts.iter().find(|s| s.to_str().unwrap().to_lowercase().contains(preferred))
+        .or(scripts.first()) else {
+        return Vec::new();
+    };
+    let Ok(content) = std::fs::read_to_string(script) else {
+        return Vec::new();
+    };
+    return parse_run_env_path(&content);
+}
+
+/// Add bin dirs of the run env to `packages`. The run env also has dirs that CMake doesn't use, like bin dirs of
+/// transitive dependencies, which belong to the package whose folder contains them. Dirs of other packages are
+/// named by the path of the Conan 1 cache, or by their folder in the Conan 2 cache.
+fn add_run_env_dirs(packages: &mut Vec<ConanPackage>, dirs: Vec<PathBuf>) {
+    for dir in dirs {
+        if packages.iter().any(|p| p.bin_dirs.contains(&dir)) {
+            continue;
+        }
+        if let Some(package) = packages.iter_mut().find(|p| p.folder.as_ref().is_some_and(|f| dir.starts_with(f))) {
+            package.bin_dirs.push(dir);
+            continue;
+        }
+        if let Some((name, version, folder)) = conan1_reference(&dir) {
+            packages.push(ConanPackage { name, version: Some(version), bin_dirs: vec![dir], folder: Some(folder) });
+            continue;
+        }
+        // Packages are at <cache>/p/b/<name><hash>/p/bin
+        let folder = dir.ancestors().skip(1).find(|d| d.file_name().is_some_and(|n| n != "p" && n != "b"));
+        let name = folder.and_then(|d| d.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| dir.to_string_lossy().to_string());
+        eprintln!("Warning: the Conan package of \"{}\" is not in the CMakeDeps files, it is named {name} by its folder",
+                  dir.display());
+        packages.push(ConanPackage { name, version: None, bin_dirs: vec![dir.clone()], folder: None });
+    }
+}
+
+/// Name, version and folder of a package in the Conan 1 cache, which is at
+/// `<home>/.conan/data/<name>/<version>/<user>/<channel>/package/<package id>`
+fn conan1_reference(dir: &Path) -> Option<(String, String, PathBuf)> {
+    let folder = dir.ancestors().find(|d| d.parent().and_then(|p| p.file_name()).is_some_and(|n| n == "package"))?;
+    let mut parts = folder.ancestors().skip(2).map(|d| d.file_name().map(|n| n.to_string_lossy().to_string()));
+    let (_channel, _user, version, name, data) = (parts.next()??, parts.next()??, parts.next()??, parts.next()??,
+                                                  parts.next()??);
+    if data != "data" {
+        return None;
+    }
+    return Some((name, version, folder.to_path_buf()));
+}
+
+/// Dirs prepended to PATH by `set "PATH=...;%PATH%"` or `export PATH="...:$PATH"`
+fn parse_run_env_path(content: &str) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let (value, separator) = if let Some(value) = line.strip_prefix("set \"PATH=") {
+            (value.trim_end_matches('"'), ';')
+        } else if let Some(value) = line.strip_prefix("export PATH=\"") {
+            (value.trim_end_matches('"'), ':')
+        } else {
+            continue;
+        };
+        for entry in value.split(separator) {
+            // Skip the existing PATH, and anything else that is not expanded
+            if entry.is_empty() || entry.contains('%') || entry.contains('$') {
+                continue;
+            }
+            let entry = PathBuf::from(entry);
+            if !dirs.contains(&entry) {
+                dirs.push(entry);
+            }
+        }
+    }
+    return dirs;
+}
+
+/// The package whose bin dir contains `file`
+pub fn find_package<'a>(packages: &'a [ConanPackage], file: &Path) -> Option<&'a ConanPackage> {
+    let dir = file.parent()?;
+    return packages.iter().find(|p| p.bin_dirs.iter().any(|bin| bin == dir));
+}
+
+/// Print the Conan package that provides each resolved dll
+pub fn print_report(packages: &[ConanPackage], resolved: &BTreeMap<String, LockedDll>) {
+    println!("Dlls provided by Conan packages:");
+    for (name, dll) in resolved {
+        if let Some(package) = find_package(packages, Path::new(&dll.source)) {
+            println!("  {name} <- {}", package.reference());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZLIB_DATA: &str = r#"########### AGGREGATED COMPONENTS AND DEPENDENCIES FOR THE MULTI CONFIG #####################
+set(zlib_COMPONENT_NAMES "")
+set(zlib_FIND_DEPENDENCY_NAMES )
+
+########### VARIABLES #######################################################################
+set(zlib_PACKAGE_FOLDER_RELEASE "C:/Users/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p")
+set(zlib_BUILD_MODULES_PATHS_RELEASE )
+
+set(zlib_INCLUDE_DIRS_RELEASE "${zlib_PACKAGE_FOLDER_RELEASE}/include")
+set(zlib_LIB_DIRS_RELEASE "${zlib_PACKAGE_FOLDER_RELEASE}/lib")
+set(zlib_BIN_DIRS_RELEASE "${zlib_PACKAGE_FOLDER_RELEASE}/bin")
+set(zlib_LIBRARY_TYPE_RELEASE SHARED)
+"#;
+
+    #[test]
+    fn cmake_data_of_release() {
+        assert_eq!(parse_cmake_data(ZLIB_DATA), Some(CmakeData {
+            name: "zlib".to_string(),
+            config: "RELEASE".to_string(),
+            package_folder: "C:/Users/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p".to_string(),
+            bin_dirs: vec![PathBuf::from("C:/Users/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p/bin")],
+        }));
+        assert_eq!(parse_cmake_data("set(CMAKE_FIND_PACKAGE_PREFER_CONFIG ON)"), None);
+    }
+
+    #[test]
+    fn run_env_path_of_bat_and_sh() {
+        let bat = r#"@echo off
+chcp 65001 > nul
+set "PATH=C:\Users\me\.conan2\p\b\zlib4f8a2c1d0e9b7\p\bin;C:\Users\me\.conan2\p\b\libpn1c2d3e4f5a6b\p\bin;%PATH%"
+"#;
+        assert_eq!(parse_run_env_path(bat), [
+            PathBuf::from(r"C:\Users\me\.conan2\p\b\zlib4f8a2c1d0e9b7\p\bin"),
+            PathBuf::from(r"C:\Users\me\.conan2\p\b\libpn1c2d3e4f5a6b\p\bin"),
+        ]);
+        let sh = r#"export PATH="/home/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p/bin:$PATH"
+export LD_LIBRARY_PATH="/home/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p/lib:$LD_LIBRARY_PATH"
+"#;
+        assert_eq!(parse_run_env_path(sh), [PathBuf::from("/home/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p/bin")]);
+    }
+
+    #[test]
+    fn run_env_dirs_join_packages_of_cmake_deps() {
+        let mut packages = vec![ConanPackage {
+            name: "zlib".to_string(),
+            version: Some("1.3.1".to_string()),
+            bin_dirs: Vec::new(),
+            folder: Some(PathBuf::from("/home/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p")),
+        }];
+        add_run_env_dirs(&mut packages, vec![
+            PathBuf::from("/home/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p/bin"),
+            PathBuf::from("/home/me/.conan/data/libpng/1.6.43/_/_/package/0a1b2c3d/bin"),
+            PathBuf::from("/home/me/.conan2/p/b/bzip23c4d5e6f7a8b/p/bin"),
+        ]);
+        let references: Vec<String> = packages.iter().map(|p| p.reference()).collect();
+        assert_eq!(references, ["zlib/1.3.1", "libpng/1.6.43", "bzip23c4d5e6f7a8b"]);
+        assert_eq!(packages[0].bin_dirs, [PathBuf::from("/home/me/.conan2/p/b/zlib4f8a2c1d0e9b7/p/bin")]);
+        assert_eq!(packages[1].folder, Some(PathBuf::from("/home/me/.conan/data/libpng/1.6.43/_/_/package/0a1b2c3d")));
+    }
+}
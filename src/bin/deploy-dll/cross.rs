@@ -0,0 +1,184 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Target triplets of mingw-w64 toolchains, whose objdump is preferred on non-Windows hosts
+const MINGW_TRIPLETS: [&str; 3] = ["x86_64-w64-mingw32", "i686-w64-mingw32", "aarch64-w64-mingw32"];
+
+/// Find executable `name` in `path`, which is the value of `PATH`
+pub fn find_in_path(name: &str, path: &OsStr) -> Option<PathBuf> {
+    for dir in std::env::split_paths(path) {
+        let mut candidates = vec![dir.join(name)];
+        if cfg!(target_os = "windows") {
+            candidates.push(dir.join(format!("{name}.exe")));
+        }
+        if let Some(found) = candidates.into_iter().find(crate::is_file) {
+            return Some(found);
+        }
+    }
+    return None;
+}
+
+/// Names of objdump to look for in `PATH`. On other hosts, objdump of mingw-w64 comes first, since the one of the
+/// host may not read PE files.
+pub fn objdump_names() -> Vec<String> {
+    let mut names = Vec::new();
+    if !cfg!(target_os = "windows") {
+        names.extend(MINGW_TRIPLETS.iter().map(|triplet| format!("{triplet}-objdump")));
+    }
+    names.push("objdump".to_string());
+    return names;
+}
+
+/// The mingw-w64 triplet of a binary format given by objdump
+pub fn triplet_of(binary_format: &str) -> Option<&'static str> {
+    return match binary_format {
+        "pei-x86-64" => Some("x86_64-w64-mingw32"),
+        "pei-i386" => Some("i686-w64-mingw32"),
+        "pei-aarch64-little" => Some("aarch64-w64-mingw32"),
+        _ => None,
+    };
+}
+
+/// Version and thread model of a gcc dir, like `12-posix` or `13.2.1`
+fn gcc_dir_key(name: &str) -> (Vec<u32>, bool) {
+    let (version, thread_model) = name.split_once('-').unwrap_or((name, ""));
+    let version = version.split('.').map(|n| n.parse().unwrap_or(0)).collect();
+    return (version, thread_model == "posix");
+}
+
+/// Dirs of gcc runtime dlls like libstdc++-6.dll, newest version first, and the posix thread model before win32
+fn gcc_dirs(root: &Path, triplet: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(root.join("usr/lib/gcc").join(triplet)) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort_by_key(|d| std::cmp::Reverse(gcc_dir_key(d.file_name().unwrap().to_str().unwrap_or_default())));
+    return dirs;
+}
+
+/// Dirs of mingw-w64 runtime dlls under `root` for `triplet`: the sysroot `bin` of Debian and Arch, the one of
+/// Fedora, and the dirs of gcc runtime dlls
+pub fn sysroot_bin_dirs(root: &Path, triplet: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![
+        root.join("usr").join(triplet).join("bin"),
+        root.join("usr").join(triplet).join("sys-root/mingw/bin"),
+    ];
+    dirs.extend(gcc_dirs(root, triplet));
+    dirs.retain(crate::can_be_dir);
+    return dirs;
+}
+
+/// Dirs of runtime dlls of a mingw-w64 toolchain installed at `sysroot`, like llvm-mingw in `/opt/llvm-mingw`
+pub fn toolchain_bin_dirs(sysroot: &Path, triplet: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![sysroot.join(triplet).join("bin"), sysroot.join("bin")];
+    dirs.retain(crate::can_be_dir);
+    return dirs;
+}
+
+/// Find `name` in `dir`, ignoring case on case-sensitive file systems like the loader of Windows does
+pub fn file_in_dir(dir: &Path, name: &str) -> PathBuf {
+    let exact = dir.join(name);
+    if cfg!(target_os = "windows") || crate::is_file(&exact) {
+        return exact;
+    }
+    let Some(file_name) = exact.file_name().and_then(|n| n.to_str()).map(|n| n.to_string()) else {
+        return exact;
+    };
+    let Some(parent) = exact.parent() else {
+        return exact;
+    };
+    let found = std::fs::read_dir(parent).ok().and_then(|entries| {
+        return entries.filter_map(|e| e.ok())
+            .find(|e| e.file_name().to_str().is_some_and(|n| n.eq_ignore_ascii_case(&file_name)))
+            .map(|e| e.path());
+    });
+    return found.unwrap_or(exact);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty dir for a test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("deploy-dll-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        return dir;
+    }
+
+    fn touch(file: &Path) {
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, b"").unwrap();
+    }
+
+    #[test]
+    fn find_in_path_splits_path_of_host() {
+        let dir = test_dir("path");
+        touch(&dir.join("second/x86_64-w64-mingw32-objdump"));
+        let path = std::env::join_paths([dir.join("first"), dir.join("second")]).unwrap();
+        assert_eq!(find_in_path("x86_64-w64-mingw32-objdump", &path), Some(dir.join("second/x86_64-w64-mingw32-objdump")));
+        assert_eq!(find_in_path("objdump", &path), None);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn mingw_objdump_comes_first() {
+        let names = objdump_names();
+        assert_eq!(names.first().map(|n| n.as_str()), Some("x86_64-w64-mingw32-objdump"));
+        assert_eq!(names.last().map(|n| n.as_str()), Some("objdump"));
+    }
+
+    #[test]
+    fn triplets_of_formats() {
+        assert_eq!(triplet_of("pei-x86-64"), Some("x86_64-w64-mingw32"));
+        assert_eq!(triplet_of("pei-i386"), Some("i686-w64-mingw32"));
+        assert_eq!(triplet_of("elf64-x86-64"), None);
+    }
+
+    #[test]
+    fn sysroot_bin_dirs_of_debian() {
+        let root = test_dir("debian");
+        for dir in ["usr/x86_64-w64-mingw32/bin", "usr/lib/gcc/x86_64-w64-mingw32/10-win32",
+                    "usr/lib/gcc/x86_64-w64-mingw32/12-win32", "usr/lib/gcc/x86_64-w64-mingw32/12-posix",
+                    "usr/lib/gcc/i686-w64-mingw32/12-posix"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let dirs = sysroot_bin_dirs(&root, "x86_64-w64-mingw32");
+        assert_eq!(dirs, vec![
+            root.join("usr/x86_64-w64-mingw32/bin"),
+            root.join("usr/lib/gcc/x86_64-w64-mingw32/12-posix"),
+            root.join("usr/lib/gcc/x86_64-w64-mingw32/12-win32"),
+            root.join("usr/lib/gcc/x86_64-w64-mingw32/10-win32"),
+        ]);
+    }
+
+    #[test]
+    fn sysroot_bin_dirs_of_fedora() {
+        let root = test_dir("fedora");
+        std::fs::create_dir_all(root.join("usr/x86_64-w64-mingw32/sys-root/mingw/bin")).unwrap();
+        std::fs::create_dir_all(root.join("usr/lib/gcc/x86_64-w64-mingw32/13.2.1")).unwrap();
+        std::fs::create_dir_all(root.join("usr/lib/gcc/x86_64-w64-mingw32/9.1.0")).unwrap();
+        let dirs = sysroot_bin_dirs(&root, "x86_64-w64-mingw32");
+        assert_eq!(dirs, vec![
+            root.join("usr/x86_64-w64-mingw32/sys-root/mingw/bin"),
+            root.join("usr/lib/gcc/x86_64-w64-mingw32/13.2.1"),
+            root.join("usr/lib/gcc/x86_64-w64-mingw32/9.1.0"),
+        ]);
+        assert!(sysroot_bin_dirs(&root, "i686-w64-mingw32").is_empty());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn file_in_dir_ignores_case() {
+        let dir = test_dir("case");
+        touch(&dir.join("Qt6Core.dll"));
+        touch(&dir.join("platforms/qwindows.dll"));
+        assert_eq!(file_in_dir(&dir, "qt6core.dll"), dir.join("Qt6Core.dll"));
+        assert_eq!(file_in_dir(&dir, "platforms/QWindows.dll"), dir.join("platforms/qwindows.dll"));
+        assert_eq!(file_in_dir(&dir, "missing.dll"), dir.join("missing.dll"));
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Dlls in a subdirectory are found through a private side-by-side assembly:
+// `app.exe.manifest` declares a dependency on assembly `<subdir>`, and `<subdir>/<subdir>.manifest`
+// lists the dlls in it. The loader then resolves those dlls through the activation context of the exe.
+
+const RT_MANIFEST_ID: &str = "ID: 0x000018";
+
+/// Check that the subdirectory name can be used as the name of a private assembly.
+pub fn validate_subdir_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("the name is empty".to_string());
+    }
+    if name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(format!("\"{name}\" is not a single directory name"));
+    }
+    if name.contains(|c: char| c.is_whitespace()) {
+        return Err(format!("\"{name}\" contains whitespace, which is not allowed in assembly names"));
+    }
+    return Ok(());
+}
+
+/// Whether the PE file contains an RT_MANIFEST resource. Windows ignores external manifests of such exe.
+pub fn has_embedded_manifest(file: &str, objdump_file: &str) -> bool {
+    let output = Command::new(objdump_file).args(["-p", file]).output()
+        .unwrap_or_else(|_| panic!("Failed to run objdump at {}", objdump_file));
+    let output = String::from_utf8_lossy(&output.stdout).replace('\r', "");
+
+    // Entries of the type table are the least indented ones
+    let entries: Vec<(usize, &str)> = output.split('\n')
+        .filter_map(|line| line.find("Entry: ").map(|loc| (loc, line)))
+        .collect();
+    let Some(type_level) = entries.iter().map(|(indent, _)| *indent).min() else {
+        return false;
+    };
+    return entries.iter()
+        .any(|(indent, line)| *indent == type_level && line.contains(RT_MANIFEST_ID));
+}
+
+/// Check whether dlls in `subdir` can be found when `target` is started. Returns the reason if not.
+pub fn check_target(target: &str, target_dir: &Path, subdir: &str, objdump_file: &str) -> Result<(), String> {
+    let target_path = Path::new(target);
+    if !target.to_lowercase().ends_with(".exe") {
+        return Err(format!("\"{target}\" is not an exe, dlls and their activation contexts can't use private assemblies"));
+    }
+    if target_path.parent() != Some(target_dir) {
+        return Err(format!("\"{target}\" is not in \"{}\", use --copy-target to stage it", target_dir.display()));
+    }
+    if has_embedded_manifest(target, objdump_file) {
+        return Err(format!("\"{target}\" has an embedded manifest, so the external manifest will be ignored. \
+        Add a dependency on assembly \"{subdir}\" to the embedded manifest, or embed the generated one with \
+        `mt.exe -manifest {target}.manifest -outputresource:{target};1`"));
+    }
+
+    // The loader probes `<appdir>/<name>.dll` before `<appdir>/<name>/<name>.manifest`
+    let mut shadow = target_dir.to_path_buf();
+    shadow.push(format!("{subdir}.dll"));
+    if crate::is_file(&shadow) {
+        return Err(format!("\"{}\" shadows the private assembly \"{subdir}\"", shadow.display()));
+    }
+    return Ok(());
+}
+
+/// Location of the external manifest of `exe`
+pub fn application_manifest_location(exe: &str) -> PathBuf {
+    return PathBuf::from(format!("{exe}.manifest"));
+}
+
+/// Write `<exe>.manifest` that depends on the private assembly `subdir`.
+pub fn write_application_manifest(exe: &str, subdir: &str) -> PathBuf {
+    let loc = application_manifest_location(exe);
+    let content = format!(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+  <dependency>
+    <dependentAssembly>
+      <assemblyIdentity type="win32" name="{subdir}" version="1.0.0.0"/>
+    </dependentAssembly>
+  </dependency>
+</assembly>
+"#);
+    std::fs::write(&loc, content).expect("Failed to write application manifest");
+    return loc;
+}
+
+/// Write `<subdir>/<subdir>.manifest` that lists every dll in `dll_dir`.
+pub fn write_assembly_manifest(dll_dir: &Path, subdir: &str) -> PathBuf {
+    let mut dlls: Vec<String> = std::fs::read_dir(dll_dir).expect("Failed to list dll subdir")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_str().unwrap().to_string())
+        .filter(|name| name.to_lowercase().ends_with(".dll"))
+        .collect();
+    dlls.sort();
+
+    let mut content = format!(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+  <assemblyIdentity type="win32" name="{subdir}" version="1.0.0.0"/>
+"#);
+    for dll in &dlls {
+        content.push_str(&format!("  <file name=\"{dll}\"/>\n"));
+    }
+    content.push_str("</assembly>\n");
+
+    let mut loc = dll_dir.to_path_buf();
+    loc.push(format!("{subdir}.manifest"));
+    std::fs::write(&loc, content).expect("Failed to write assembly manifest");
+    return loc;
+}
+
+/// Value of attribute `name` in the first tag of `xml`
+fn attribute<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let tag = &xml[..xml.find('>')?];
+    let start = tag.find(&format!("{name}=\""))? + name.len() + 2;
+    let end = start + tag[start..].find('"')?;
+    return Some(&tag[start..end]);
+}
+
+/// Names of the assemblies that an application manifest depends on
+fn dependent_assemblies(manifest: &str) -> Vec<String> {
+    return manifest.split("<dependentAssembly").skip(1)
+        .filter_map(|block| {
+            let identity = &block[block.find("<assemblyIdentity")?..];
+            return attribute(identity, "name").map(|name| name.to_string());
+        })
+        .collect();
+}
+
+/// Dll names listed by `<file name="..."/>` of an assembly manifest
+fn assembly_files(manifest: &str) -> Vec<String> {
+    return manifest.split("<file").skip(1)
+        .filter_map(|tag| attribute(tag, "name").map(|name| name.to_string()))
+        .collect();
+}
+
+/// Dlls that `exe` finds through the private assemblies of its external manifest, by lower-case name.
+/// Those are redirected by the activation context, before the dlls next to the exe are looked at.
+pub fn private_assembly_dlls(exe: &Path) -> BTreeMap<String, PathBuf> {
+    let mut dlls = BTreeMap::new();
+    let Ok(app_manifest) = std::fs::read_to_string(application_manifest_location(exe.to_str().unwrap())) else {
+        return dlls;
+    };
+    let app_dir = exe.parent().unwrap();
+    for assembly in dependent_assemblies(&app_manifest) {
+        let assembly_dir = app_dir.join(&assembly);
+        let Ok(manifest) = std::fs::read_to_string(assembly_dir.join(format!("{assembly}.manifest"))) else {
+            continue;
+        };
+        for file in assembly_files(&manifest) {
+            let path = assembly_dir.join(&file);
+            if crate::is_file(&path) {
+                dlls.insert(file.to_lowercase(), path);
+            }
+        }
+    }
+    return dlls;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_generated_manifests() {
+        let app_manifest = r#"<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+  <assemblyIdentity type="win32" name="app" version="1.0.0.0"/>
+  <dependency>
+    <dependentAssembly>
+      <assemblyIdentity type="win32" name="lib" version="1.0.0.0"/>
+    </dependentAssembly>
+  </dependency>
+</assembly>"#;
+        assert_eq!(dependent_assemblies(app_manifest), ["lib"]);
+
+        let assembly_manifest = r#"<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+  <assemblyIdentity type="win32" name="lib" version="1.0.0.0"/>
+  <file name="libaa.dll"/>
+  <file name="LibBB.dll"/>
+</assembly>"#;
+        assert_eq!(assembly_files(assembly_manifest), ["libaa.dll", "LibBB.dll"]);
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::Path;
+
+/// Signature of VS_FIXEDFILEINFO
+const FIXED_FILE_INFO_SIGNATURE: [u8; 4] = [0xBD, 0x04, 0xEF, 0xFE];
+
+/// Read the file version from the version resource of a PE file, like "6.5.3.0".
+pub fn file_version(file: &Path) -> Option<String> {
+    let content = std::fs::read(file).ok()?;
+    let key: Vec<u8> = "VS_VERSION_INFO".encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+
+    // The fixed file info follows the VS_VERSION_INFO key, aligned to 32 bits
+    let key_loc = content.windows(key.len()).position(|w| w == key.as_slice())?;
+    let search_end = (key_loc + key.len() + 64).min(content.len());
+    let sig_loc = key_loc + content[key_loc..search_end].windows(4).position(|w| w == FIXED_FILE_INFO_SIGNATURE)?;
+
+    let read_u32 = |offset: usize| -> Option<u32> {
+        let bytes = content.get(sig_loc + offset..sig_loc + offset + 4)?;
+        return Some(u32::from_le_bytes(bytes.try_into().unwrap()));
+    };
+    let ms = read_u32(8)?;
+    let ls = read_u32(12)?;
+    return Some(format!("{}.{}.{}.{}", ms >> 16, ms & 0xffff, ls >> 16, ls & 0xffff));
+}
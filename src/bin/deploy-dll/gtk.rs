@@ -0,0 +1,65 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Dir of gdk-pixbuf loaders and their cache, relative to the prefix
+pub const LOADERS_DIR: &str = "lib/gdk-pixbuf-2.0/2.10.0/loaders";
+pub const LOADERS_CACHE: &str = "lib/gdk-pixbuf-2.0/2.10.0/loaders.cache";
+/// Compiled GSettings schemas, relative to the prefix
+pub const COMPILED_SCHEMAS: &str = "share/glib-2.0/schemas/gschemas.compiled";
+/// Icon themes that GTK falls back to
+pub const ICON_THEMES: [&str; 2] = ["hicolor", "Adwaita"];
+
+/// Dlls of GLib and GTK, any of which tells where the prefix is
+const PREFIX_DLLS: [&str; 4] = ["libgdk_pixbuf-2.0-0.dll", "libgtk-4-1.dll", "libgtk-3-0.dll", "libglib-2.0-0.dll"];
+
+/// Whether dll `name` can tell the GTK prefix
+pub fn is_prefix_dll(name: &str) -> bool {
+    return PREFIX_DLLS.contains(&name.to_lowercase().as_str());
+}
+
+/// GLib finds its data relative to the dir of its dll: the parent of it if it's named `bin`, or the dir itself.
+pub fn data_prefix(dll_dir: &Path) -> PathBuf {
+    let is_bin = dll_dir.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.eq_ignore_ascii_case("bin"));
+    return match dll_dir.parent() {
+        Some(parent) if is_bin => parent.to_path_buf(),
+        _ => dll_dir.to_path_buf(),
+    };
+}
+
+/// gdk-pixbuf loader dlls of a prefix, sorted
+pub fn loaders(prefix: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(prefix.join(LOADERS_DIR)) else {
+        return Vec::new();
+    };
+    let mut loaders: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dll")))
+        .collect();
+    loaders.sort();
+    return loaders;
+}
+
+/// Rewrite loaders.cache so that each loader is given relative to the prefix, which gdk-pixbuf resolves against
+/// its own prefix at runtime. Entries of loaders not in `deployed` are dropped.
+pub fn rewrite_loaders_cache(content: &str, deployed: &BTreeSet<String>) -> String {
+    let mut result = String::new();
+    // Entries are separated by empty lines, the first line of each is the quoted path of loader
+    for entry in content.split("\n\n") {
+        let entry = entry.trim_matches('\n');
+        if entry.is_empty() {
+            continue;
+        }
+        let mut lines: Vec<String> = entry.lines().map(|l| l.trim_end_matches('\r').to_string()).collect();
+        if let Some(index) = lines.iter().position(|l| l.starts_with('"')) {
+            let path = lines[index].trim_matches('"').replace('\\', "/");
+            let filename = path.rsplit('/').next().unwrap_or_default().to_string();
+            if !deployed.contains(&filename.to_lowercase()) {
+                continue;
+            }
+            lines[index] = format!("\"{LOADERS_DIR}/{filename}\"");
+        }
+        result.push_str(&lines.join("\n"));
+        result.push_str("\n\n");
+    }
+    return result;
+}
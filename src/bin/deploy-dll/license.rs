@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::{msys2, vcpkg, Context};
+
+/// Name of the directory in deploy dir that licenses are copied to
+pub const LICENSE_DIR: &str = "licenses";
+
+/// License files of a dll, and the directory name under `licenses/` to copy them to
+struct LicenseSource {
+    package: String,
+    files: Vec<PathBuf>,
+}
+
+fn files_in_dir(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    return files;
+}
+
+/// vcpkg installs the license of each port to `share/<port>/copyright`
+fn vcpkg_license(source: &Path, vcpkg_owners: &vcpkg::OwnerIndex) -> Option<LicenseSource> {
+    let owner = vcpkg_owners.find_owner(source)?;
+    let files: Vec<PathBuf> = owner.files.iter()
+        .filter(|f| f.ends_with(&format!("/share/{}/copyright", owner.port)))
+        .map(|f| owner.installed_dir.join(f))
+        .filter(crate::is_file)
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+    return Some(LicenseSource { package: owner.port, files });
+}
+
+/// MSYS2 packages install licenses to `<env>/share/licenses/<name>/`
+fn msys2_license(source: &Path, msys2_owners: &msys2::OwnerIndex) -> Option<LicenseSource> {
+    let owner = msys2_owners.find_owner(source)?;
+    let files: Vec<PathBuf> = owner.files.iter()
+        .filter(|f| f.contains("/share/licenses/"))
+        .map(|f| owner.root.join(f))
+        .filter(crate::is_file)
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+    return Some(LicenseSource { package: owner.package, files });
+}
+
+/// Qt installers put licenses into `<root>/Licenses`, and Qt builds into `<prefix>/LICENSES`
+fn qt_license(source: &Path, vcpkg_owners: &vcpkg::OwnerIndex, msys2_owners: &msys2::OwnerIndex) -> Option<LicenseSource> {
+    let package = crate::package::detect_package(source.to_str()?, vcpkg_owners, msys2_owners)?;
+    if package.ecosystem != "qt" {
+        return None;
+    }
+    for dir in source.ancestors().skip(2).take(4) {
+        for name in ["Licenses", "LICENSES", "licenses"] {
+            let files: Vec<PathBuf> = files_in_dir(&dir.join(name));
+            if !files.is_empty() {
+                return Some(LicenseSource { package: "qt".to_string(), files });
+            }
+        }
+    }
+    return None;
+}
+
+/// Package layouts like Conan's keep licenses in `<package>/licenses`, next to `<package>/bin`
+fn sibling_license(source: &Path) -> Option<LicenseSource> {
+    let package_dir = source.parent()?.parent()?;
+    let files = files_in_dir(&package_dir.join("licenses"));
+    if files.is_empty() {
+        return None;
+    }
+    let package = package_dir.file_name()?.to_str()?.to_string();
+    return Some(LicenseSource { package, files });
+}
+
+fn find_license(source: &Path, vcpkg_owners: &vcpkg::OwnerIndex, msys2_owners: &msys2::OwnerIndex)
+                -> Option<LicenseSource> {
+    return vcpkg_license(source, vcpkg_owners)
+        .or_else(|| msys2_license(source, msys2_owners))
+        .or_else(|| qt_license(source, vcpkg_owners, msys2_owners))
+        .or_else(|| sibling_license(source));
+}
+
+/// Copy licenses of every resolved dll into `licenses/`, and write `licenses/report.txt`.
+/// Dlls whose license can't be found are listed in the report.
+pub fn collect_licenses(context: &mut Context) {
+    let license_dir = context.root_dir.join(LICENSE_DIR);
+    let mut found: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut unresolved = Vec::new();
+
+    let resolved: Vec<(String, String)> = context.resolved.iter()
+        .map(|(name, r)| (name.clone(), r.source.clone()))
+        .collect();
+    for (name, source) in resolved {
+        let license = find_license(Path::new(&source), &context.vcpkg_owners, &context.msys2_owners)
+            // Licenses that are already collected are not the source of anything
+            .filter(|l| !l.files.iter().any(|f| f.starts_with(&license_dir)));
+        let Some(mut license) = license else {
+            unresolved.push(name);
+            continue;
+        };
+        // Conan package folders are named by hash, use the package name instead
+        if let Some(package) = crate::conan::find_package(&context.conan_packages, Path::new(&source)) {
+            license.package = package.name.clone();
+        }
+
+        let package_dir = license_dir.join(&license.package);
+        std::fs::create_dir_all(&package_dir).expect("Failed to create license dir");
+        let mut copied = Vec::new();
+        for file in &license.files {
+            let dest = package_dir.join(file.file_name().unwrap());
+            if !crate::is_file(&dest) {
+                std::fs::copy(file, &dest).expect("Failed to copy license file");
+                context.record_copy(dest.to_str().unwrap(), file.to_str().unwrap());
+            }
+            copied.push(crate::manifest::relative_path(&license_dir, &dest));
+        }
+        found.insert(name, copied);
+    }
+
+    let mut report = String::from("License files of deployed dlls\n\n");
+    for (name, files) in &found {
+        report.push_str(&format!("{name}: {}\n", files.join(", ")));
+    }
+    if !unresolved.is_empty() {
+        report.push_str("\nUnresolved, no license file was found for these dlls:\n");
+        for name in &unresolved {
+            report.push_str(&format!("{name}\n"));
+        }
+    }
+    std::fs::create_dir_all(&license_dir).expect("Failed to create license dir");
+    let report_file = license_dir.join("report.txt");
+    std::fs::write(&report_file, report).expect("Failed to write license report");
+    context.record_copy(report_file.to_str().unwrap(), crate::manifest::GENERATED_SOURCE);
+
+    println!("Collected licenses of {} dlls into \"{}\"", found.len(), license_dir.display());
+    if !unresolved.is_empty() {
+        println!("No license found for {} dlls: {}", unresolved.len(), unresolved.join(", "));
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use serde::{Deserialize, Serialize};
+
+const LOCKFILE_VERSION: u32 = 1;
+
+/// Records where every deployed dll was resolved from, like Cargo.lock does for crates.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Lockfile {
+    version: u32,
+    /// Lower-case dll name -> resolution. Sorted, so that the lockfile is stable.
+    pub dlls: BTreeMap<String, LockedDll>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockedDll {
+    /// The file that the dll was copied from. Relative to the lockfile when saved, absolute when loaded
+    pub source: String,
+    /// SHA-256 of the dll
+    pub sha256: String,
+    /// Package manager that the dll comes from, like vcpkg, msys2 or conan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecosystem: Option<String>,
+    /// Name of the package that the dll comes from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// Version of the package that the dll comes from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl LockedDll {
+    /// Fields of `resolved` that differ from this locked resolution, like `sha256: abc -> def`
+    pub fn changes(&self, resolved: &LockedDll) -> Vec<String> {
+        let mut changes = Vec::new();
+        let same_source = if cfg!(target_os = "windows") {
+            self.source.eq_ignore_ascii_case(&resolved.source)
+        } else {
+            self.source == resolved.source
+        };
+        if !same_source {
+            changes.push(format!("source: \"{}\" -> \"{}\"", self.source, resolved.source));
+        }
+        if self.sha256 != resolved.sha256 {
+            changes.push(format!("sha256: {} -> {}", self.sha256, resolved.sha256));
+        }
+        let fields = [
+            ("ecosystem", &self.ecosystem, &resolved.ecosystem),
+            ("package", &self.package, &resolved.package),
+            ("version", &self.version, &resolved.version),
+        ];
+        for (field, locked, resolved) in fields {
+            if locked != resolved {
+                let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "none".to_string());
+                changes.push(format!("{field}: {} -> {}", show(locked), show(resolved)));
+            }
+        }
+        return changes;
+    }
+}
+
+impl Lockfile {
+    pub fn new(dlls: BTreeMap<String, LockedDll>) -> Lockfile {
+        return Lockfile {
+            version: LOCKFILE_VERSION,
+            dlls,
+        };
+    }
+
+    pub fn load(file: &str) -> Lockfile {
+        let content = match std::fs::read_to_string(file) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Failed to read lockfile \"{file}\" because {e}. Run `deploy-dll lock` to create it");
+                exit(10);
+            }
+        };
+        let mut lockfile = match serde_json::from_str::<Lockfile>(&content) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("Failed to parse lockfile \"{file}\" because {e}");
+                exit(10);
+            }
+        };
+        let dir = lockfile_dir(file);
+        for dll in lockfile.dlls.values_mut() {
+            dll.source = crate::manifest::absolute_path(&dir.join(&dll.source)).to_str().unwrap().replace('\\', "/");
+        }
+        return lockfile;
+    }
+
+    /// Save the lockfile, with sources relative to it so that the project can be moved.
+    pub fn save(&self, file: &str) {
+        let dir = lockfile_dir(file);
+        let mut saved = Lockfile::new(self.dlls.clone());
+        for dll in saved.dlls.values_mut() {
+            if let Some(relative) = crate::manifest::relative_path_from(&dir, Path::new(&dll.source)) {
+                dll.source = relative;
+            }
+        }
+        let mut content = serde_json::to_string_pretty(&saved).expect("Serialize lockfile");
+        content.push('\n');
+        if let Err(e) = std::fs::write(file, content) {
+            eprintln!("Failed to write lockfile \"{file}\" because {e}");
+            exit(10);
+        }
+    }
+
+    /// Exit if `dll` resolved differently from the lockfile
+    pub fn verify(&self, name: &str, dll: &LockedDll) {
+        let Some(locked) = self.dlls.get(name) else {
+            eprintln!("\"{name}\" (from \"{}\") is not in the lockfile. Run `deploy-dll lock` to update it", dll.source);
+            exit(10);
+        };
+        let changes = locked.changes(dll);
+        if !changes.is_empty() {
+            eprintln!("\"{name}\" resolved differently from the lockfile. Run `deploy-dll lock` to update it");
+            for change in changes {
+                eprintln!("  {change}");
+            }
+            exit(10);
+        }
+    }
+
+    /// Exit if some locked dlls were not resolved
+    pub fn verify_all_resolved(&self, resolved: &BTreeMap<String, LockedDll>) {
+        let missing: Vec<&String> = self.dlls.keys().filter(|name| !resolved.contains_key(*name)).collect();
+        if !missing.is_empty() {
+            eprintln!("Dlls {:?} are in the lockfile, but no longer required. Run `deploy-dll lock` to update it", missing);
+            exit(10);
+        }
+    }
+}
+
+/// Resolution of a dll that is copied from `source`, or found at `source` in the deploy dir.
+pub fn resolve(source: &str, package: Option<crate::package::Package>) -> LockedDll {
+    let source_path = crate::manifest::absolute_path(Path::new(source));
+    let (ecosystem, package, version) = match package {
+        Some(p) => (Some(p.ecosystem), p.name, p.version),
+        None => (None, None, None),
+    };
+    return LockedDll {
+        source: source_path.to_str().unwrap().replace('\\', "/"),
+        sha256: crate::manifest::file_sha256(&source_path),
+        ecosystem,
+        package,
+        version,
+    };
+}
+
+/// Directory that the sources in lockfile `file` are relative to
+fn lockfile_dir(file: &str) -> PathBuf {
+    let parent = Path::new(file).parent().unwrap_or(Path::new(""));
+    return crate::manifest::absolute_path(parent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked(source: &str, package: Option<&str>) -> LockedDll {
+        return LockedDll {
+            source: source.to_string(),
+            sha256: "8f434346648f6b96df89dda901c5176b10a6d83961dd3c1ac88b59b2dc327aa4".to_string(),
+            ecosystem: package.map(|_| "vcpkg".to_string()),
+            package: package.map(|p| p.to_string()),
+            version: package.map(|_| "1.3.1".to_string()),
+        };
+    }
+
+    #[test]
+    fn same_resolution_has_no_changes() {
+        let dll = locked("/opt/vcpkg/installed/x64-windows/bin/zlib1.dll", Some("zlib"));
+        assert!(dll.changes(&dll.clone()).is_empty());
+    }
+
+    #[test]
+    fn source_differs_with_same_hash() {
+        let lock = locked("/opt/vcpkg/installed/x64-windows/bin/zlib1.dll", Some("zlib"));
+        let resolved = locked("/usr/local/bin/zlib1.dll", None);
+        assert_eq!(lock.changes(&resolved), [
+            "source: \"/opt/vcpkg/installed/x64-windows/bin/zlib1.dll\" -> \"/usr/local/bin/zlib1.dll\"",
+            "ecosystem: vcpkg -> none",
+            "package: zlib -> none",
+            "version: 1.3.1 -> none",
+        ]);
+    }
+
+    #[test]
+    fn hash_differs_with_same_source() {
+        let lock = locked("/opt/libs/zlib1.dll", None);
+        let resolved = LockedDll { sha256: "0".repeat(64), ..lock.clone() };
+        assert_eq!(lock.changes(&resolved), [format!("sha256: {} -> {}", lock.sha256, "0".repeat(64))]);
+    }
+}
@@ -0,0 +1,2052 @@
+#![allow(clippy::needless_return)]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, exit};
+use std::time::Duration;
+use clap::{Parser, Subcommand};
+
+mod baseline;
+mod build_type;
+mod conan;
+mod cross;
+mod dll_subdir;
+mod file_version;
+mod gtk;
+mod license;
+mod lock;
+mod manifest;
+mod msys2;
+mod pack;
+mod package;
+mod python;
+mod qml;
+mod qt;
+mod rdata;
+mod sbom;
+mod smoke;
+mod targets;
+mod tree;
+mod ucrt;
+mod vc_redist;
+mod vcpkg;
+mod verify;
+
+use baseline::{Baseline, TargetOs};
+use build_type::BuildType;
+use lock::{LockedDll, Lockfile};
+use manifest::Manifest;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = "Deploy dll for exe or dll.",
+    args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<SubCommand>,
+
+    #[command(flatten)]
+    args: Args,
+}
+
+#[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
+enum SubCommand {
+    /// Remove dlls deployed by previous runs, as recorded in the deployment manifest. Modified files are kept.
+    Clean {
+        /// The directory that contains the deployment manifest
+        dir: String,
+
+        /// Show verbose information during execution
+        #[arg(long, default_value_t = false)]
+        verbose: bool,
+    },
+    /// Deploy dlls, then pack the binary and its dlls into a reproducible archive
+    Pack {
+        #[command(flatten)]
+        args: Args,
+
+        /// Format of the archive
+        #[arg(long, value_enum, default_value_t = pack::ArchiveFormat::Zip)]
+        format: pack::ArchiveFormat,
+        /// Location of the archive. Its SHA-256 is written to `<output>.sha256`
+        #[arg(short, long)]
+        output: String,
+        /// Directories relative to the deploy dir to pack as a whole, for example `platforms` for Qt
+        #[arg(long)]
+        include_dir: Vec<String>,
+    },
+    /// Deploy dlls, and record where every dll was resolved from in the lockfile
+    Lock {
+        #[command(flatten)]
+        args: Args,
+    },
+    /// Manage system dll baselines
+    Baseline {
+        #[command(subcommand)]
+        command: BaselineCommand,
+    },
+    /// Run a deployed exe with only the dlls next to it and the system, and report the dlls that fail to load
+    Smoke {
+        /// The deployed exe to run
+        binary_file: String,
+        /// Arguments of the exe, after `--`
+        #[arg(last = true)]
+        app_args: Vec<String>,
+
+        /// Run with Wine in a throwaway prefix, which is required on hosts other than Windows
+        #[arg(long, default_value_t = false)]
+        wine: bool,
+        /// The Wine executable, like `wine64`
+        #[arg(long, default_value_t = String::from("wine"), requires = "wine")]
+        wine_binary: String,
+        /// Extra dll overrides for WINEDLLOVERRIDES, like `msvcp140=n` to refuse the builtin dll of Wine
+        #[arg(long, requires = "wine")]
+        dll_override: Vec<String>,
+        /// Seconds to wait for the exe. If it's still running, it has started and is killed
+        #[arg(long, default_value_t = 10)]
+        timeout: u64,
+        /// The system that the exe was deployed for, to tell why a dll was not deployed
+        #[arg(long, value_enum)]
+        target_os: Option<TargetOs>,
+        /// A file of system dlls to use instead of --target-os, one name per line
+        #[arg(long, conflicts_with = "target_os")]
+        system_baseline: Option<String>,
+        /// Show verbose information during execution
+        #[arg(long, default_value_t = false)]
+        verbose: bool,
+    },
+    /// Check that a deployed binary only requires dlls next to it and dlls of the system, without copying anything
+    Verify {
+        /// The deployed exe or dll to check
+        binary_file: String,
+
+        /// Location of dumpbin file. Valid values: [auto] [system] [builtin] path
+        #[arg(long, default_value_t = String::from("[auto]"))]
+        objdump_file: String,
+        /// Dll files that are expected to be missing
+        #[arg(long)]
+        ignore: Vec<String>,
+        /// The system to check for, whose system dlls don't have to be next to the binary
+        #[arg(long, value_enum)]
+        target_os: Option<TargetOs>,
+        /// A file of system dlls to use instead of --target-os, one name per line
+        #[arg(long, conflicts_with = "target_os")]
+        system_baseline: Option<String>,
+        /// The Visual C/C++ runtime has to be next to the binary, rather than installed on the system
+        #[arg(long, default_value_t = false)]
+        app_local_vc_redist: bool,
+        /// The Universal CRT has to be next to the binary, for systems older than Windows 10
+        #[arg(long, default_value_t = false)]
+        app_local_ucrt: bool,
+        /// Show verbose information during execution
+        #[arg(long, default_value_t = false)]
+        verbose: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BaselineCommand {
+    /// Record the dlls of a system dir, a Windows dir or a Wine prefix into a baseline file for --system-baseline
+    Generate {
+        /// System32 or SysWOW64 of a mounted Windows image, the Windows dir that contains them, or a Wine prefix
+        dir: String,
+
+        /// Location of the baseline file
+        #[arg(short, long)]
+        output: String,
+        /// Record exported symbols of each dll, so that `verify` checks the symbols imported from system dlls
+        #[arg(long, default_value_t = false)]
+        exports: bool,
+        /// Record the file version of each dll
+        #[arg(long, default_value_t = false)]
+        versions: bool,
+        /// Location of dumpbin file. Valid values: [auto] [system] [builtin] path
+        #[arg(long, default_value_t = String::from("[auto]"))]
+        objdump_file: String,
+        /// Show verbose information during execution
+        #[arg(long, default_value_t = false)]
+        verbose: bool,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct Args {
+    /// The target files to deploy dll for. These can be exes or dlls, in different dirs. Glob patterns like
+    /// `bin/*.exe` are expanded
+    #[arg(required_unless_present = "tree")]
+    binary_files: Vec<String>,
+
+    /// Relative paths to DLLs that is linked optionally, for example: `imageformats/jpeg.dll` for Qt
+    #[arg(long)]
+    optional_dlls:Vec<String>,
+
+    /// Do not search in system variable PATH
+    #[arg(long, default_value_t = false)]
+    skip_env_path: bool,
+
+    /// Copy Microsoft Visual C/C++ redistributable dlls.
+    #[arg(long, default_value_t = false)]
+    copy_vc_redist: bool,
+    /// `VC/Redist/MSVC/<version>` dir of Visual Studio to copy redistributable dlls from.
+    /// By default it is taken from `VCToolsRedistDir`, or found by vswhere
+    #[arg(long)]
+    vc_redist_dir: Option<String>,
+    /// Copy the redistributable installer like `vc_redist.x64.exe` instead of the dlls
+    #[arg(long, default_value_t = false, conflicts_with = "copy_vc_redist")]
+    vc_redist_installer: bool,
+    /// Copy the Universal CRT redistributable of the Windows SDK, ucrtbase.dll and its api-ms-win-* forwarders,
+    /// if any api-ms-win-crt-* dll is imported. For systems older than Windows 10 without the Universal CRT update
+    #[arg(long, default_value_t = false)]
+    app_local_ucrt: bool,
+    /// Root of Windows SDK, like `C:/Program Files (x86)/Windows Kits/10`. By default it is taken from `WindowsSdkDir`
+    #[arg(long, requires = "app_local_ucrt")]
+    windows_sdk_dir: Option<String>,
+
+    /// Show verbose information during execution
+    #[arg(long, default_value_t = false)]
+    verbose: bool,
+
+    /// Search for dll in those dirs
+    #[arg(long)]
+    shallow_search_dir: Vec<String>,
+    /// Disable shallow search
+    #[arg(long, default_value_t = false)]
+    no_shallow_search: bool,
+
+    /// Search for dll recursively in those dirs
+    #[arg(long)]
+    deep_search_dir: Vec<String>,
+    /// Disable recursive search
+    #[arg(long, default_value_t = false)]
+    no_deep_search: bool,
+
+    /// CMAKE_PREFIX_PATH for cmake to search for packages
+    #[arg(long)]
+    cmake_prefix_path: Vec<String>,
+    /// Root of vcpkg, or a vcpkg_installed dir of manifest mode. Dlls are searched in `installed/<triplet>/bin` first
+    #[arg(long)]
+    vcpkg_root: Option<String>,
+    /// vcpkg triplet, deduced from the architecture of target binary by default
+    #[arg(long, requires = "vcpkg_root")]
+    vcpkg_triplet: Option<String>,
+    /// MSYS2 root like `C:/msys64`, or an environment prefix like `C:/msys64/ucrt64`. Dlls are searched in its bin dir first
+    #[arg(long)]
+    msys2_prefix: Option<String>,
+    /// Sysroot of a mingw-w64 toolchain like `/opt/llvm-mingw`. Its runtime dlls are searched first. On hosts other
+    /// than Windows, the sysroots of mingw-w64 packages like `/usr/x86_64-w64-mingw32` are searched by default
+    #[arg(long)]
+    mingw_sysroot: Option<String>,
+    /// Conan generators folder, with conanbuildinfo.json, conanbuildinfo.txt, CMakeDeps files or
+    /// conanrunenv scripts of VirtualRunEnv. Dlls are searched in the bin dirs of its packages first
+    #[arg(long)]
+    conan_dir: Option<String>,
+    /// Build type of target binary, which selects `bin` or `debug/bin` of vcpkg, and the configuration of Conan.
+    /// Dlls of the same build type are preferred
+    #[arg(long, value_enum, default_value_t = BuildType::Auto)]
+    build_type: BuildType,
+    /// Fail if debug and release dlls are mixed, instead of warning
+    #[arg(long, default_value_t = false)]
+    deny_mixed_build_types: bool,
+    /// Dll files that won't be deployed
+    #[arg(long)]
+    ignore: Vec<String>,
+    /// The system to deploy for, whose system dlls are not deployed
+    #[arg(long, value_enum)]
+    target_os: Option<TargetOs>,
+    /// A file of system dlls to use instead of --target-os, one name per line
+    #[arg(long, conflicts_with = "target_os")]
+    system_baseline: Option<String>,
+    /// Scan strings in `.rdata` of binaries for dll names, which may be loaded by `LoadLibrary`, and report them
+    #[arg(long, default_value_t = false)]
+    scan_dll_strings: bool,
+    /// Deploy the dlls reported by --scan-dll-strings that can be found
+    #[arg(long, default_value_t = false, requires = "scan_dll_strings")]
+    deploy_dll_strings: bool,
+
+    /// Copy the Qt plugins required by imported Qt modules, like `platforms/qwindows.dll` for Qt6Gui.dll
+    #[arg(long, default_value_t = false)]
+    qt_plugins: bool,
+    /// Qt prefix to copy plugins and QML modules from, like `C:/Qt/6.6.0/msvc2019_64`.
+    /// By default it is where Qt6Core.dll is found
+    #[arg(long)]
+    qt_prefix: Option<String>,
+    /// Copy Qt plugins into this dir instead of next to the binary, and write a `qt.conf` that points to it
+    #[arg(long, requires = "qt_plugins")]
+    qt_plugin_dir: Option<String>,
+    /// Dirs of .qml sources to scan for imports. Imported QML modules are copied from the Qt prefix into `qml/`
+    #[arg(long)]
+    qml_dir: Vec<String>,
+    /// JSON output of qmlimportscanner, whose modules are copied like the imports found in --qml-dir
+    #[arg(long)]
+    qml_imports: Option<String>,
+
+    /// Prefix of the embedded Python interpreter, like `C:/Python312`, where python3XX.dll and dlls of its `DLLs` are
+    /// searched. Extension modules (.pyd) next to the binary and in its `DLLs` dir are deployed as well
+    #[arg(long)]
+    python_prefix: Option<String>,
+    /// Copy the zipped standard library, `python3XX._pth` and standard extension modules from the embeddable package
+    /// of Python in --python-prefix
+    #[arg(long, default_value_t = false, requires = "python_prefix")]
+    python_embed: bool,
+
+    /// Copy runtime files of GTK: gdk-pixbuf loaders with their cache, compiled GSettings schemas and icon themes
+    #[arg(long, default_value_t = false)]
+    gtk: bool,
+    /// Prefix to copy GTK runtime files from, like `C:/msys64/ucrt64`. By default it is where GLib is found
+    #[arg(long, requires = "gtk")]
+    gtk_prefix: Option<String>,
+
+    /// Location of dumpbin file. Valid values: [auto] [system] [builtin] path
+    #[arg(long, default_value_t = String::from("[auto]"))]
+    objdump_file: String,
+    /// If one or more dll failed to be found, skip it and go on
+    #[arg(long, default_value_t = false)]
+    allow_missing: bool,
+
+    /// Deploy dlls into this directory instead of the directory of binary file
+    #[arg(long)]
+    output_dir: Option<String>,
+    /// Copy the target binaries and optional dlls into the output dir as well, and deploy for the copies
+    #[arg(long, default_value_t = false, requires = "output_dir")]
+    copy_target: bool,
+    /// Deploy for every exe, dll and pyd in this dir recursively. Missing dlls are put next to the binaries that
+    /// require them, or into --output-dir
+    #[arg(long, conflicts_with_all = ["binary_files", "copy_target", "dll_subdir"])]
+    tree: Option<String>,
+    /// Deploy dlls into this subdirectory, and generate manifests so that Windows can find them
+    #[arg(long)]
+    dll_subdir: Option<String>,
+
+    /// Location of the lockfile, written by `deploy-dll lock` and checked by --locked
+    #[arg(long, default_value_t = String::from("deploy-dll.lock"))]
+    lockfile: String,
+    /// Fail if any dll resolves differently from the lockfile
+    #[arg(long, default_value_t = false)]
+    locked: bool,
+
+    /// Write a CycloneDX SBOM of the binary and its deployed dlls to this file
+    #[arg(long)]
+    sbom: Option<String>,
+    /// Copy license files of deployed dlls from vcpkg, MSYS2 and Qt installations into `licenses/`
+    #[arg(long, default_value_t = false)]
+    collect_licenses: bool,
+}
+
+#[derive(Default)]
+struct Context {
+    deployed_dlls:BTreeSet<PathBuf>,
+    manifest: Manifest,
+    /// The directory of deployment manifest, which is also where the target binaries live
+    root_dir: PathBuf,
+    /// Target binaries given by user, after they are staged
+    root_binaries: Vec<String>,
+    /// Where each dll was resolved from, by lower-case name
+    resolved: BTreeMap<String, LockedDll>,
+    /// The lockfile to check against, if --locked
+    lockfile: Option<Lockfile>,
+    /// Packages of --conan-dir
+    conan_packages: Vec<conan::ConanPackage>,
+    /// Build type of the target binaries, if it can be told
+    build_type: Option<BuildType>,
+    /// Build type of each deployed binary that can be told
+    binary_build_types: BTreeMap<String, BuildType>,
+    /// Runtime files that are not binaries, like .qml files of QML modules
+    data_files: BTreeSet<PathBuf>,
+    /// Dll names found in strings of binaries, and the binaries that contain them
+    dll_strings: BTreeMap<String, BTreeSet<String>>,
+    /// System dlls of the target system, or None to check the system dirs of the host
+    baseline: Option<Baseline>,
+    /// Lower-case names of dlls imported by each binary, by its absolute path
+    imports: BTreeMap<PathBuf, Vec<String>>,
+    /// Files that each binary loads its deployed imports from, by absolute paths
+    loads: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    /// Dirs of the exes that load the dll being deployed for, where its imports are found as well
+    app_dirs: Vec<PathBuf>,
+    /// Only resolve dlls and check them against the lockfile, without copying anything
+    dry_run: bool,
+    /// Owners of files in vcpkg installed dirs, shared by package detection, reports and licenses
+    vcpkg_owners: vcpkg::OwnerIndex,
+    /// Owners of files in MSYS2 installations, shared by package detection, reports, licenses and SBOM
+    msys2_owners: msys2::OwnerIndex,
+}
+
+impl Context {
+    /// Record a copied file in the deployment manifest, so that `deploy-dll clean` can remove it.
+    /// Returns SHA-256 of the copy.
+    fn record_copy(&mut self, file: &str, source: &str) -> String {
+        let sha256 = self.manifest.record(&self.root_dir, Path::new(file), source);
+        self.manifest.save(&self.root_dir);
+        return sha256;
+    }
+
+    /// Record that dll `name` resolved to `source`, and check it against the lockfile.
+    fn record_resolution(&mut self, name: &str, source: &str) {
+        if self.resolved.contains_key(name) {
+            return;
+        }
+        let resolution = lock::resolve(source, self.package_of(source));
+        if let Some(lockfile) = &self.lockfile {
+            lockfile.verify(name, &resolution);
+        }
+        self.resolved.insert(name.to_string(), resolution);
+    }
+
+    /// Where a dll that already exists in deploy dir came from
+    fn source_of_existing(&self, file: &str) -> String {
+        let relative = manifest::relative_path(&self.root_dir, Path::new(file));
+        return match self.manifest.entry(&relative) {
+            Some(entry) => entry.source.clone(),
+            None => file.to_string(),
+        };
+    }
+
+    /// The package of a dll, from --conan-dir or deduced from its source location
+    fn package_of(&self, source: &str) -> Option<package::Package> {
+        if let Some(package) = conan::find_package(&self.conan_packages, Path::new(source)) {
+            return Some(package::Package {
+                ecosystem: "conan".to_string(),
+                name: Some(package.name.clone()),
+                version: package.version.clone(),
+                detail: None,
+            });
+        }
+        return package::detect_package(source, &self.vcpkg_owners, &self.msys2_owners);
+    }
+}
+
+
+fn existing_var_path(dest: &mut Vec<String>) {
+    if let Some(path) = std::env::var_os("PATH") {
+        for path in std::env::split_paths(&path) {
+            if !can_be_dir(&path) {
+                continue;
+            }
+            dest.push(path.to_str().unwrap().to_string());
+        }
+    }
+}
+
+fn get_system_objdump()->Option<String> {
+    let path = std::env::var_os("PATH")?;
+    for name in cross::objdump_names() {
+        if let Some(found) = cross::find_in_path(&name, &path) {
+            return Some(found.to_str().unwrap().to_string());
+        }
+    }
+    return None;
+}
+
+fn get_objdump_file(input:&str)->String {
+    if input=="[system]" {
+        if let Some(loc)=get_system_objdump() {
+            return loc;
+        }
+        eprintln!("Failed to find objdump in your system");
+        exit(2);
+    }
+
+    if input == "[builtin]" {
+        let current_exe = std::env::current_exe().expect("Get current exe name");
+        //println!("current_exe = {}",current_exe.to_str().unwrap());
+        let install_prefix = current_exe.parent().expect("Get parent dir of current exe");
+        //println!("install_prefix = {}",install_prefix.to_str().unwrap());
+        let mut p = install_prefix.to_path_buf();
+        if cfg!(target_os = "windows"){
+            p.push("objdump.exe");
+        }else {
+            p.push("objdump");
+        }
+
+        if !is_file(&p) {
+            eprintln!("Builtin objdump executable {} not found",p.display());
+            exit(3);
+        }
+
+        //println!("objdump path = {}",p.to_str().unwrap());
+        return p.to_str().unwrap().to_string();
+    }
+
+    if input=="[auto]"  {
+        if let Some(loc)=get_system_objdump() {
+            return loc;
+        }
+        return get_objdump_file("[builtin]");
+    }
+
+    if !is_file(&input) {
+        eprintln!("Given objdump file {} doesn't exist",input);
+        exit(4);
+    }
+
+    return input.to_string();
+}
+
+impl Args {
+    /// The first target binary
+    fn binary_file(&self) -> &str {
+        return self.binary_files.first().expect("Binary file is required");
+    }
+
+    /// The directory of the (first) target binary
+    fn binary_dir(&self) -> PathBuf {
+        return PathBuf::from(self.binary_file()).parent().unwrap().to_path_buf();
+    }
+
+    /// The directory that dlls are deployed to
+    fn target_dir(&self) -> PathBuf {
+        return match (&self.output_dir, &self.tree) {
+            (Some(dir), _) => PathBuf::from(dir),
+            (None, Some(tree)) => PathBuf::from(tree),
+            (None, None) => self.binary_dir(),
+        };
+    }
+
+    fn objdump_file(&self) -> String {
+        return get_objdump_file(&self.objdump_file);
+    }
+
+    fn shallow_search_dirs(&self) -> Vec<String> {
+        let mut vec = self.shallow_search_dir.clone();
+        if self.tree.is_some() {
+            // Dlls in the tree are deployed to the binaries in other dirs of it
+            vec.splice(0..0, tree::binary_dirs(&self.binary_files));
+        } else if self.output_dir.is_some() {
+            // Dlls next to the original binary are found by the loader first, so do we
+            vec.insert(0, self.binary_dir().to_str().unwrap().to_string());
+        }
+        self.existing_cmake_prefix_path(&mut vec);
+
+        if cfg!(target_os = "windows") && !self.skip_env_path {
+            existing_var_path(&mut vec);
+        }
+
+        return vec;
+    }
+
+    fn existing_cmake_prefix_path(&self, dest: &mut Vec<String>) {
+        for path in &self.cmake_prefix_path {
+            for path in path.split(';') {
+                let path = format!("{path}/bin");
+                if can_be_dir(&path) {
+                    dest.push(path);
+                }
+            }
+        }
+    }
+
+    fn deep_search_dirs(&self) -> Vec<String> {
+        let mut vec = self.deep_search_dir.clone();
+        self.existing_cmake_prefix_path(&mut vec);
+
+        if cfg!(target_os = "windows") && !self.skip_env_path {
+            existing_var_path(&mut vec);
+        }
+
+        return vec;
+    }
+
+
+    /// Absolute paths of target binaries, with glob patterns expanded. Paths are sorted and deduplicated.
+    fn target_binary_abs_path(&self)->Vec<String> {
+        let mut paths =BTreeSet::new();
+        let current_dir = std::env::current_dir().unwrap();
+        for binary_file in &self.binary_files {
+            let path = current_dir.join(binary_file);
+            if binary_file.contains('*') {
+                let glob_str=path.to_str().expect("Convert globbing path into string");
+                let err=format!("Globbing with \"{glob_str}\"");
+                for entry in glob::glob(glob_str).expect(&err) {
+                    paths.insert(entry.expect(&err));
+                }
+            }else {
+                paths.insert(path);
+            }
+        }
+
+        let mut paths_str =Vec::with_capacity(paths.len());
+        for p in &paths {
+            paths_str.push(p.to_str().expect("Convert PathBuf to String").to_string());
+        }
+
+        return paths_str;
+    }
+
+    fn optional_dll_abs_path(&self)->Vec<String> {
+
+        let mut paths=BTreeSet::new();
+
+        let target_dir_path = PathBuf::from(self.binary_file()).parent().unwrap().to_path_buf();
+        for opt_dll in &self.optional_dlls {
+            for opt_dll in opt_dll.split(';') {
+                let mut target_dir_path=target_dir_path.clone();
+                target_dir_path.push(opt_dll);
+                if opt_dll.contains('*') { // is globbing
+                    for entry in glob::glob(target_dir_path.to_str().unwrap()).expect("Globbing") {
+                        paths.insert(entry.expect("Globbing"));
+                    }
+                }else {
+                    paths.insert(target_dir_path);
+                }
+            }
+        }
+
+        let mut paths_str=Vec::with_capacity(paths.len());
+
+        for p in &paths {
+            paths_str.push(p.to_str().unwrap().to_string());
+        }
+
+
+        return paths_str;
+    }
+}
+
+fn parse_output_single_line(output: &str) -> &str {
+    let fail_msg = format!("Failed to parse dll name from output \"{output}\"");
+    let loc1 = output.find("dll name: ").expect(&fail_msg);
+    let loc2 = output.find(".dll").expect(&fail_msg);
+
+    let loc1 = loc1 + "dll name: ".len();
+    if loc1 + 1 >= loc2 {
+        eprintln!("{}", fail_msg);
+        exit(8);
+    }
+
+    return &output[loc1..loc2];
+}
+
+fn get_dependencies(file: &str, objdump_file: &str) -> Vec<String> {
+    let output = Command::new(objdump_file).args([file, "-x", "--section=.rdata"]).output()
+        .unwrap_or_else(|_| panic!("Failed to run objdump at {}", objdump_file));
+
+    if !output.status.success() {
+        eprintln!("{} {} -x failed with error code {}", objdump_file, file, output.status);
+        eprintln!("The std error is: {}", String::from_utf8(output.stderr).unwrap());
+        exit(1);
+    }
+
+    let output = String::from_utf8(output.stdout)
+        .expect("Failed to convert output to utf8")
+        .replace('\r',"")
+        .to_lowercase();
+    let split = output.split("\n");
+    let mut dlls = Vec::with_capacity(split.clone().count());
+    //let regex=Regex::new(r"dll name: (.+)\.dll").unwrap();
+    for line in split {
+        if !line.contains("dll name: ") {
+            continue;
+        }
+
+        let mut str = parse_output_single_line(line).to_string();
+        str.push_str(".dll");
+        dlls.push(str);
+    }
+    return dlls;
+}
+
+fn is_vc_redist_dll(name: &str) -> bool {
+    let name=name.to_lowercase();
+    // Debug runtimes like ucrtbased.dll come with Visual Studio, they are never deployed
+    if build_type::is_debug_crt(&name) {
+        return true;
+    }
+    if name.starts_with("vcruntime") {
+        return true;
+    }
+    if name.starts_with("msvcrt") {
+        return true;
+    }
+    if name.starts_with("msvcp") {
+        return true;
+    }
+    if name.starts_with("concrt") || name.starts_with("vcomp") {
+        return true;
+    }
+
+    return false;
+}
+
+/// Whether dll `name` is skipped by name, because it's a part of the system or ignored by user
+fn is_never_deployed(name: &str, args: &Args, baseline: Option<&Baseline>) -> bool {
+    return args.ignore.iter().any(|ignored| ignored.eq_ignore_ascii_case(name))
+        || ucrt::is_api_set(name) || ucrt::is_ucrt_dll(name) || is_vc_redist_dll(name) || is_system_dll(name, baseline);
+}
+
+/// Whether dll `name` comes with the system, by the baseline, or by looking into the system dirs of the host if
+/// there is no baseline
+fn is_system_dll(name: &str, baseline: Option<&Baseline>) -> bool {
+    if let Some(baseline) = baseline {
+        return baseline.contains(name);
+    }
+    let system_prefices = [
+        "C:/Windows/",
+        "C:/Windows/system32/",
+        "C:/Windows/System32/Wbem/",
+        "C:/Windows/System32/WindowsPowerShell/v1.0/",
+        "C:/Windows/System32/OpenSSH/"];
+    for prefix in system_prefices {
+        let filename = format!("{prefix}{name}");
+        if is_file(&filename) {
+            return true;
+        }
+    }
+    return false;
+}
+
+fn can_be_dir<P: AsRef<Path>>(path: &P) -> bool {
+    if let Ok(md) = std::fs::metadata(path) {
+        if md.is_dir() {
+            return true;
+        }
+    }
+    return false;
+}
+
+fn is_file<P: AsRef<Path>>(path: &P) -> bool {
+    if let Ok(md) = std::fs::metadata(path) {
+        return md.is_file();
+    }
+    return false;
+}
+
+fn get_file_format(filename: &str, objdump_loc: &str) -> String {
+    let output = Command::new(objdump_loc).args(["-f", filename]).output().unwrap();
+
+    if !output.status.success() {
+        let command = format!("{objdump_loc} -f {filename}");
+        eprintln!("{command} failed with error code {:?}", output.status.to_string());
+        eprintln!("It failed with std error output: \n{}", String::from_utf8(output.stderr).unwrap());
+        exit(1);
+    }
+
+    let output = String::from_utf8(output.stdout).unwrap().replace('\r', "");
+
+
+    //println!("{}",output);
+    for line in output.split("\n") {
+        //println!("Line {idx}: {line}");
+        if let Some(loc) = line.rfind("file format ") {
+            let loc = loc + "file format ".len();
+            return line[loc..line.len()].to_string();
+        }
+    }
+    eprintln!("Failed to parse file format of {filename} from objdump output, it says: \n{output}");
+    exit(1);
+}
+
+type DllValidator<'a> = dyn Fn(&Path) -> Result<(), String> + 'a;
+
+fn validate_dll(dll_loc: &Path, args: &Args, custom_validator: Option<&DllValidator<'_>>) -> bool {
+    if !is_file(&dll_loc) {
+        return false;
+    }
+    if let Some(validate) = &custom_validator {
+        if let Err(reason) = validate(dll_loc) {
+            if args.verbose {
+                println!("Skipped \"{}\" because {reason}", dll_loc.display());
+            }
+            return false;
+        }
+    }
+    return true;
+}
+
+fn search_dll_deep(name: &str, args: &Args, validate: Option<&DllValidator<'_>>) -> Option<String> {
+    use walkdir::WalkDir;
+    for dir in args.deep_search_dirs() {
+        for entry in WalkDir::new(dir) {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(e) => {
+                    if args.verbose {
+                        println!("Failed to search in \"{:?}\" because {}", e.path(), e);
+                    }
+                    continue;
+                }
+            };
+            let mut loc = entry.path().to_path_buf();
+            loc.push(name);
+
+            if !validate_dll(&loc, args, validate) {
+                continue;
+            }
+
+            return Some(loc.to_str().unwrap().to_string());
+        }
+    }
+
+    return None;
+}
+
+
+fn search_dll_shallow(name: &str, args: &Args, validate: Option<&DllValidator<'_>>) -> Option<String> {
+    for path in args.shallow_search_dirs() {
+        let loc = cross::file_in_dir(Path::new(&path), name);
+
+        if !validate_dll(&loc, args, validate) {
+            continue;
+        }
+
+        return Some(loc.to_str().unwrap().to_string());
+    }
+    return None;
+}
+
+/// Copy dll `name` from `location` into `target_dir`, and record where it comes from. Returns the copy.
+/// `name` can have a dir, like `platforms/qwindows.dll`, which is not a part of the copy. The copy is named after
+/// `location`, which may differ from `name` in case.
+fn copy_dll(name: &str, location: &str, target_dir: &str, context: &mut Context) -> String {
+    let dest = format!("{target_dir}/{}", Path::new(location).file_name().unwrap().to_str().unwrap());
+    context.record_resolution(name, location);
+    if context.dry_run {
+        // Walk the dependencies of the source instead
+        return location.to_string();
+    }
+    println!("Copying \"{location}\" to \"{target_dir}\"");
+    std::fs::copy(location, &dest).expect("Failed to copy dll");
+    let copied_sha256 = context.record_copy(&dest, location);
+    if copied_sha256 != context.resolved[name].sha256 {
+        eprintln!("SHA-256 of \"{dest}\" differs from \"{location}\" after copying");
+        exit(1);
+    }
+    return dest;
+}
+
+fn check_arch(dll: &Path, objdump_file: &str, binary_format: &str) -> Result<(), String> {
+    let format = get_file_format(dll.to_str().unwrap(), objdump_file);
+    if format != binary_format {
+        return Err(format!("DLL architecture mismatch. Expected {binary_format}, but found {format}"));
+    }
+    return Ok(());
+}
+
+/// Shallow search first, then deep search
+fn search_dll(name: &str, args: &Args, validate: &DllValidator<'_>) -> Option<String> {
+    if !args.no_shallow_search {
+        if let Some(location) = search_dll_shallow(name, args, Some(validate)) {
+            return Some(location);
+        }
+    }
+    if !args.no_deep_search {
+        return search_dll_deep(name, args, Some(validate));
+    }
+    return None;
+}
+
+fn deploy_dll(target_binary: &str, target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args,context: &mut Context) {
+    if args.verbose {
+        println!("Deploying for \"{target_binary}\" at \"{target_dir}\"");
+    }
+    let deps = get_dependencies(target_binary, objdump_file);
+    if args.verbose {
+        println!("\"{target_binary}\" requires {:?}",deps)
+    }
+    let target_name = Path::new(target_binary).file_name().unwrap().to_str().unwrap();
+    let target_path = manifest::absolute_path(Path::new(target_binary));
+    context.imports.insert(target_path.clone(), deps.iter().map(|dep| dep.to_lowercase()).collect());
+    if let Some(build_type) = build_type::build_type_of(target_name, &deps) {
+        context.binary_build_types.insert(target_binary.to_string(), build_type);
+    }
+    if args.scan_dll_strings {
+        for name in rdata::dll_strings(target_binary, objdump_file) {
+            if name == target_name.to_lowercase() || deps.iter().any(|dep| dep.eq_ignore_ascii_case(&name))
+                || is_never_deployed(&name, args, context.baseline.as_ref()) {
+                continue;
+            }
+            context.dll_strings.entry(name).or_default().insert(target_name.to_string());
+        }
+    }
+
+    for dep in &deps {
+        if args.verbose {
+            println!("Searching {dep} for \"{target_binary}\"")
+        }
+        if context.dry_run && context.resolved.contains_key(dep.as_str()) {
+            continue;
+        }
+
+        let mut expected_filename = cross::file_in_dir(Path::new(target_dir), dep).to_str().unwrap().to_string();
+        if !is_file(&expected_filename) {
+            // A dll next to the target binaries is found by loader as well
+            let in_root_dir = cross::file_in_dir(&context.root_dir, dep);
+            let in_app_dir = context.app_dirs.iter().map(|dir| cross::file_in_dir(dir, dep)).find(is_file);
+            if is_file(&in_root_dir) {
+                expected_filename = in_root_dir.to_str().unwrap().to_string();
+            } else if let Some(in_app_dir) = in_app_dir {
+                expected_filename = in_app_dir.to_str().unwrap().to_string();
+            }
+        }
+        let expected_filename_path=PathBuf::from(expected_filename.clone());
+        let dll_exist=std::fs::metadata(&expected_filename).is_ok();
+
+        if dll_exist && args.verbose && context.deployed_dlls.contains(&expected_filename_path) {
+            context.loads.entry(target_path.clone()).or_default().insert(manifest::absolute_path(&expected_filename_path));
+            println!("{expected_filename} is already deployed");
+            continue;
+        }
+
+        if args.ignore.contains(dep) {
+            // The dll is assigned to be ignored
+            if args.verbose {
+                println!("Skip {dep} because it is assigned to be ignored");
+            }
+            continue;
+        }
+
+        if build_type::is_debug_crt(dep) {
+            // Debug runtimes are not redistributable, they come with Visual Studio
+            if args.verbose {
+                println!("Skip debug C/C++ runtime dll {dep}");
+            }
+            continue;
+        }
+
+        let is_ucrt=ucrt::is_ucrt_dll(dep);
+        if is_ucrt && !args.app_local_ucrt {
+            // Universal CRT is part of Windows 10 and later
+            if args.verbose {
+                println!("Skip Universal CRT dll {dep}");
+            }
+            continue;
+        }
+        if ucrt::is_api_set(dep) && !is_ucrt {
+            if args.verbose {
+                println!("Skip API set {dep}");
+            }
+            continue;
+        }
+
+        let is_vc_redist=is_vc_redist_dll(dep);
+
+        if !args.copy_vc_redist && is_vc_redist {
+            // Skip vc redist dll.
+            if args.verbose {
+                println!("Skip VC redistributable dll {dep}");
+            }
+            continue;
+        }
+
+        if is_system_dll(dep, context.baseline.as_ref()) && !is_vc_redist && !is_ucrt {
+            // Skip system dll
+            if args.verbose {
+                println!("Skip system dll {dep}");
+            }
+            continue;
+        }
+
+
+        if !dll_exist {
+
+            if args.verbose {
+                println!("Searching {dep} for {target_binary}");
+            }
+            // search for it
+            let validate_arch = |loc: &Path| check_arch(loc, objdump_file, binary_format);
+            let expected_build_type = context.build_type;
+            let validate_build_type = |loc: &Path| {
+                validate_arch(loc)?;
+                let Some(expected) = expected_build_type else {
+                    return Ok(());
+                };
+                let name = loc.file_name().unwrap().to_str().unwrap();
+                match build_type::build_type_of(name, &get_dependencies(loc.to_str().unwrap(), objdump_file)) {
+                    Some(found) if found != expected => Err(format!("it is a {found:?} build, but the target is a {expected:?} build")),
+                    _ => Ok(()),
+                }
+            };
+
+            // Prefer the dll of the same build type, and fall back to any one
+            let mut loc = search_dll(dep, args, &validate_build_type);
+            if loc.is_none() && expected_build_type.is_some() {
+                loc = search_dll(dep, args, &validate_arch);
+            }
+
+            if let Some(location) = &loc {
+                expected_filename = copy_dll(dep, location, target_dir, context);
+            } else if args.allow_missing {
+                println!("Failed to find dll \"{dep}\", required by \"{target_binary}\"");
+                continue;
+            } else {
+                eprintln!("Failed to find dll \"{dep}\", required by \"{target_binary}\"");
+                exit(1);
+            }
+        } else if !context.resolved.contains_key(dep.as_str()) {
+            let source = context.source_of_existing(&expected_filename);
+            if is_file(&source) {
+                context.record_resolution(dep, &source);
+            } else {
+                // The original source is gone, the deployed copy is what we ship
+                context.record_resolution(dep, &expected_filename);
+            }
+        }
+
+        context.loads.entry(target_path.clone()).or_default().insert(manifest::absolute_path(Path::new(&expected_filename)));
+        deploy_dll(&expected_filename, target_dir, objdump_file, binary_format, args,context);
+    }
+    context.deployed_dlls.insert(PathBuf::from(target_binary));
+}
+
+fn main() {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(SubCommand::Clean { dir, verbose }) => manifest::clean(&dir, verbose),
+        Some(SubCommand::Pack { mut args, format, output, include_dir }) => {
+            // Everything has to be in one tree to be packed
+            if args.output_dir.is_some() {
+                args.copy_target = true;
+            }
+            let verbose = args.verbose;
+            let context = deploy(args);
+            let (archive_root, files) = pack::collect_files(&context, &include_dir);
+            pack::pack(&files, &archive_root, format, &output, verbose);
+        }
+        Some(SubCommand::Lock { args }) => {
+            let lockfile = args.lockfile.clone();
+            let context = deploy(args);
+            Lockfile::new(context.resolved).save(&lockfile);
+            println!("Wrote lockfile \"{lockfile}\"");
+        }
+        Some(SubCommand::Verify { binary_file, objdump_file, ignore, target_os, system_baseline,
+                                  app_local_vc_redist, app_local_ucrt, verbose }) => {
+            let baseline = load_baseline(target_os, system_baseline.as_deref());
+            let is_baseline = |name: &str| {
+                if ignore.iter().any(|ignored| ignored.eq_ignore_ascii_case(name)) {
+                    return true;
+                }
+                if ucrt::is_ucrt_dll(name) {
+                    return !app_local_ucrt && baseline.as_ref().is_none_or(|b| b.has_ucrt());
+                }
+                if ucrt::is_api_set(name) {
+                    return true;
+                }
+                if is_vc_redist_dll(name) {
+                    return !app_local_vc_redist;
+                }
+                return is_system_dll(name, baseline.as_ref());
+            };
+            verify_binary(&binary_file, &get_objdump_file(&objdump_file), &is_baseline, baseline.as_ref(), verbose);
+        }
+        Some(SubCommand::Baseline { command: BaselineCommand::Generate { dir, output, exports, versions, objdump_file, verbose } }) => {
+            // objdump is only needed for exports
+            let objdump_file = if exports { Some(get_objdump_file(&objdump_file)) } else { None };
+            generate_baseline(&dir, &output, objdump_file.as_deref(), versions, verbose);
+        }
+        Some(SubCommand::Smoke { binary_file, app_args, wine, wine_binary, dll_override, timeout, target_os,
+                                 system_baseline, verbose }) => {
+            let baseline = load_baseline(target_os, system_baseline.as_deref());
+            let wine = if wine { Some((wine_binary.as_str(), dll_override.as_slice())) } else { None };
+            smoke_test(&binary_file, &app_args, wine, Duration::from_secs(timeout), baseline.as_ref(), verbose);
+        }
+        None => {
+            deploy(cli.args);
+        }
+    }
+}
+
+/// Find an executable given by name or path
+fn find_executable(name: &str) -> Option<PathBuf> {
+    if name.contains(['/', '\\']) {
+        return Some(PathBuf::from(name)).filter(is_file);
+    }
+    return cross::find_in_path(name, &std::env::var_os("PATH")?);
+}
+
+/// Run `binary_file` in a clean environment, and exit if dlls fail to load. `wine` is the Wine executable and
+/// extra dll overrides to run with.
+fn smoke_test(binary_file: &str, app_args: &[String], wine: Option<(&str, &[String])>, timeout: Duration,
+              baseline: Option<&Baseline>, verbose: bool) {
+    let binary = std::env::current_dir().unwrap().join(binary_file);
+    if !is_file(&binary) {
+        eprintln!("Given target \"{}\" is not a file", binary.display());
+        exit(5);
+    }
+    let app_dir = binary.parent().unwrap();
+
+    // The prefix is removed when it goes out of scope
+    let mut _prefix = None;
+    let command = match wine {
+        Some((wine_binary, dll_overrides)) => {
+            let Some(wine) = find_executable(wine_binary) else {
+                eprintln!("Wine executable \"{wine_binary}\" is not found");
+                exit(24);
+            };
+            let wineserver = wine.with_file_name("wineserver");
+            let wineserver = if is_file(&wineserver) { Some(wineserver) } else { find_executable("wineserver") };
+            let prefix = smoke::WinePrefix::create(wineserver).unwrap_or_else(|e| {
+                eprintln!("{e}");
+                exit(24);
+            });
+            let wine_command = |program: &str| {
+                let mut command = Command::new(&wine);
+                command.arg(program)
+                    .current_dir(app_dir)
+                    .env("WINEPREFIX", &prefix.dir)
+                    .env("WINEDLLOVERRIDES", smoke::wine_dll_overrides(dll_overrides))
+                    .env("WINEDEBUG", "-all,err+module")
+                    .env("PATH", smoke::clean_path(&wine))
+                    .env_remove("WINEPATH");
+                return command;
+            };
+            println!("Creating Wine prefix \"{}\"", prefix.dir.display());
+            let mut wineboot = wine_command("wineboot");
+            wineboot.arg("--init").stdout(std::process::Stdio::null()).stderr(std::process::Stdio::null());
+            let booted = wineboot.status().is_ok_and(|status| status.success());
+            let mut command = wine_command(binary.to_str().unwrap());
+            command.args(app_args);
+            if !booted {
+                drop(prefix);
+                eprintln!("Failed to create Wine prefix with \"{}\"", wine.display());
+                exit(24);
+            }
+            _prefix = Some(prefix);
+            command
+        }
+        None => {
+            if !cfg!(target_os = "windows") {
+                eprintln!("Exes can only be run with Wine on this system, use --wine");
+                exit(24);
+            }
+            let mut command = Command::new(&binary);
+            command.args(app_args).current_dir(app_dir).env("PATH", smoke::clean_path(&binary));
+            command
+        }
+    };
+
+    println!("Running \"{}\"", binary.display());
+    let run = smoke::run(command, timeout);
+    // Stop Wine and remove the prefix before exiting
+    drop(_prefix);
+    let run = run.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(24);
+    });
+    if verbose {
+        eprint!("{}", run.stderr);
+    }
+
+    let errors = smoke::parse_loader_errors(&run.stderr);
+    let status = run.exit_code.map(|code| code as u32);
+    if errors.is_empty() && status != Some(smoke::STATUS_DLL_NOT_FOUND) && status != Some(smoke::STATUS_INVALID_IMAGE_FORMAT) {
+        match run.exit_code {
+            Some(code) => println!("\"{}\" started and exited with code {code}", binary.display()),
+            None => println!("\"{}\" started and was still running after {}s", binary.display(), timeout.as_secs()),
+        }
+        return;
+    }
+
+    eprintln!("\"{}\" failed to start, because dlls failed to load:", binary.display());
+    if errors.is_empty() {
+        eprintln!("  a dll is not found or of another architecture (status {:#x}), run with --wine to tell which one",
+                  status.unwrap_or_default());
+    }
+    let manifest = Manifest::load(app_dir);
+    for error in &errors {
+        eprintln!("  {} required by {}: {}, {}", error.dll, error.needed_by, error.reason,
+                  explain_missing(&error.dll, app_dir, manifest.as_ref(), baseline));
+    }
+    exit(24);
+}
+
+/// Why `dll` may be missing at runtime, by how deployment treats it
+fn explain_missing(dll: &str, app_dir: &Path, manifest: Option<&Manifest>, baseline: Option<&Baseline>) -> String {
+    if let Some(entry) = manifest.and_then(|m| m.entry_named(dll)) {
+        if !is_file(&app_dir.join(&entry.path)) {
+            return format!("it was deployed from \"{}\", but has been removed", entry.source);
+        }
+        return format!("it was deployed from \"{}\", check its architecture with verify", entry.source);
+    }
+    if is_file(&cross::file_in_dir(app_dir, dll)) {
+        return "it is next to the exe but was not deployed, check its architecture with verify".to_string();
+    }
+    if ucrt::is_ucrt_dll(dll) {
+        return "it is a part of the Universal CRT, which is only deployed with --app-local-ucrt".to_string();
+    }
+    if build_type::is_debug_crt(dll) {
+        return "it is a debug build of the C/C++ runtime, which only comes with Visual Studio".to_string();
+    }
+    if is_vc_redist_dll(dll) {
+        return "it is a Visual C++ runtime dll, which is only deployed with --copy-vc-redist".to_string();
+    }
+    if ucrt::is_api_set(dll) || is_system_dll(dll, baseline) {
+        return "it is considered a system dll, which the target system doesn't have".to_string();
+    }
+    return "it was not deployed, deploy again, and check --ignore".to_string();
+}
+
+/// Write the dlls of system dirs in `dir` into the baseline file `output`
+fn generate_baseline(dir: &str, output: &str, objdump_file: Option<&str>, versions: bool, verbose: bool) {
+    let dir = Path::new(dir);
+    if !can_be_dir(&dir) {
+        eprintln!("Given dir \"{}\" is not a dir", dir.display());
+        exit(22);
+    }
+    let system_dirs = baseline::system_dirs(dir);
+    let mut system_baseline = Baseline::default();
+    for system_dir in &system_dirs {
+        let dlls = baseline::dlls_in(system_dir);
+        println!("Found {} dlls in \"{}\"", dlls.len(), system_dir.display());
+        for dll in dlls {
+            if verbose {
+                println!("Recording \"{}\"", dll.display());
+            }
+            let exports = objdump_file.and_then(|objdump| match verify::exports_of(&dll, objdump) {
+                Ok(exports) => Some(exports),
+                Err(e) => {
+                    eprintln!("Warning: exports of \"{}\" are not recorded: {e}", dll.display());
+                    None
+                }
+            });
+            let system_dll = baseline::SystemDll {
+                version: if versions { file_version::file_version(&dll) } else { None },
+                exports,
+            };
+            system_baseline.insert(dll.file_name().unwrap().to_str().unwrap(), system_dll);
+        }
+    }
+    if system_baseline.is_empty() {
+        eprintln!("No dll is found in \"{}\"", dir.display());
+        exit(22);
+    }
+    let dirs: Vec<String> = system_dirs.iter().map(|d| d.display().to_string()).collect();
+    let header = format!("System dlls generated by deploy-dll from:\n{}", dirs.join("\n"));
+    if let Err(e) = std::fs::write(output, system_baseline.to_file(&header)) {
+        eprintln!("Failed to write baseline \"{output}\" because {e}");
+        exit(22);
+    }
+    println!("Wrote {} dlls to baseline \"{output}\"", system_baseline.len());
+}
+
+/// The system dll baseline given by --target-os or --system-baseline
+fn load_baseline(target_os: Option<TargetOs>, file: Option<&str>) -> Option<Baseline> {
+    return baseline::select(target_os, file).unwrap_or_else(|e| {
+        eprintln!("Failed to load system dll baseline: {e}");
+        exit(22);
+    });
+}
+
+/// Exit if `binary_file` requires dlls that are neither next to it nor part of the system
+fn verify_binary(binary_file: &str, objdump_file: &str, is_baseline: &dyn Fn(&str) -> bool, baseline: Option<&Baseline>,
+                 verbose: bool) {
+    let binary = std::env::current_dir().unwrap().join(binary_file);
+    if !is_file(&binary) {
+        eprintln!("Given target \"{}\" is not a file", binary.display());
+        exit(5);
+    }
+    let problems = verify::verify(&binary, objdump_file, is_baseline, baseline, verbose);
+    if !problems.is_empty() {
+        eprintln!("\"{}\" is not self-contained, {} problems are found:", binary.display(), problems.len());
+        for problem in &problems {
+            eprintln!("  {problem}");
+        }
+        exit(21);
+    }
+    println!("\"{}\" is self-contained", binary.display());
+}
+
+fn deploy(mut args: Args) -> Context {
+    if let Some(tree) = &args.tree {
+        let tree = std::env::current_dir().unwrap().join(tree);
+        if !can_be_dir(&tree) {
+            eprintln!("Given tree \"{}\" is not a dir", tree.display());
+            exit(5);
+        }
+        let output_dir = args.output_dir.as_ref().map(|d| std::env::current_dir().unwrap().join(d));
+        let mut deploy_dirs = vec![tree.as_path()];
+        deploy_dirs.extend(output_dir.as_deref());
+        args.binary_files = tree::discover(&tree, &deploy_dirs).iter().map(|b| b.to_str().unwrap().to_string()).collect();
+        if args.binary_files.is_empty() {
+            eprintln!("No exe, dll or pyd is found in \"{}\"", tree.display());
+            exit(5);
+        }
+        if args.verbose {
+            println!("Found {} binaries in \"{}\"", args.binary_files.len(), tree.display());
+        }
+        args.tree = Some(tree.to_str().unwrap().to_string());
+    }
+    {
+        let targets = if args.tree.is_some() {
+            // Binaries of the tree are absolute already, and in the order that they are deployed
+            args.binary_files.clone()
+        } else {
+            args.target_binary_abs_path()
+        };
+        if targets.is_empty() {
+            eprintln!("No target binary matches {:?}", args.binary_files);
+            exit(5);
+        }
+        if args.verbose && targets != args.binary_files {
+            println!("Given targets {:?} are expanded to {:?}", args.binary_files, targets);
+        }
+        args.binary_files = targets;
+
+        let dirs: BTreeSet<PathBuf> = args.binary_files.iter()
+            .map(|file| Path::new(file).parent().unwrap().to_path_buf())
+            .collect();
+        if dirs.len() > 1 && args.output_dir.is_none() && args.tree.is_none() {
+            eprintln!("Target binaries are in different dirs {dirs:?}, use --output-dir to deploy dlls into one dir, \
+                       and --copy-target to copy targets into it as well");
+            exit(5);
+        }
+
+        if let Some(output_dir) = &args.output_dir {
+            let mut abs_output_dir = std::env::current_dir().unwrap();
+            abs_output_dir.push(output_dir);
+            if let Err(e) = std::fs::create_dir_all(&abs_output_dir) {
+                eprintln!("Failed to create output dir \"{}\" because {e}", abs_output_dir.display());
+                exit(5);
+            }
+            args.output_dir = Some(abs_output_dir.to_str().unwrap().to_string());
+        }
+    }
+
+    let objdump_loc=args.objdump_file();
+    if args.verbose {
+        println!("Using objdump at {objdump_loc}");
+    }
+
+    let binary_dir_path = args.binary_dir();
+    let root_dir_path = args.target_dir();
+    let target_dir_path = match &args.dll_subdir {
+        Some(subdir) => {
+            if let Err(reason) = dll_subdir::validate_subdir_name(subdir) {
+                eprintln!("Invalid dll subdir because {reason}");
+                exit(7);
+            }
+            let dir = root_dir_path.join(subdir);
+            std::fs::create_dir_all(&dir).expect("Failed to create dll subdir");
+            dir
+        }
+        None => root_dir_path.clone(),
+    };
+    let target_dir = target_dir_path.to_str().unwrap();
+    let format = get_file_format(args.binary_file(), &args.objdump_file());
+    if args.verbose {
+        println!("Binary format: \"{format}\"");
+    }
+
+    if let Some(vcpkg_root) = args.vcpkg_root.clone() {
+        let build_type = target_build_type(&args, &objdump_loc);
+        let bin_dir = vcpkg_bin_dir(&vcpkg_root, args.vcpkg_triplet.as_deref(), &format, build_type);
+        if args.verbose {
+            println!("Build type is {build_type:?}, searching in vcpkg dir \"{}\"", bin_dir.display());
+        }
+        args.shallow_search_dir.insert(0, bin_dir.to_str().unwrap().to_string());
+    }
+
+    if let Some(msys2_prefix) = args.msys2_prefix.clone() {
+        let prefix = std::env::current_dir().unwrap().join(msys2_prefix);
+        let imports = get_dependencies(args.binary_file(), &objdump_loc);
+        let bin_dir = match msys2::bin_dir(&prefix, &format, &imports) {
+            Ok(dir) => dir,
+            Err(reason) => {
+                eprintln!("Invalid MSYS2 prefix: {reason}");
+                exit(13);
+            }
+        };
+        if args.verbose {
+            println!("Searching in MSYS2 dir \"{}\"", bin_dir.display());
+        }
+        args.shallow_search_dir.insert(0, bin_dir.to_str().unwrap().to_string());
+    }
+
+    if let Some(triplet) = cross::triplet_of(&format) {
+        if let Some(sysroot) = &args.mingw_sysroot {
+            let bin_dirs = cross::toolchain_bin_dirs(&std::env::current_dir().unwrap().join(sysroot), triplet);
+            if bin_dirs.is_empty() {
+                eprintln!("Invalid mingw-w64 sysroot: no bin dir for {triplet} is found in \"{sysroot}\"");
+                exit(23);
+            }
+            for bin_dir in bin_dirs.iter().rev() {
+                if args.verbose {
+                    println!("Searching in mingw-w64 dir \"{}\"", bin_dir.display());
+                }
+                args.shallow_search_dir.insert(0, bin_dir.to_str().unwrap().to_string());
+            }
+        } else if !cfg!(target_os = "windows") {
+            // Dlls given by the user are preferred to the ones of system packages
+            for bin_dir in cross::sysroot_bin_dirs(Path::new("/"), triplet) {
+                if args.verbose {
+                    println!("Searching in mingw-w64 dir \"{}\"", bin_dir.display());
+                }
+                args.shallow_search_dir.push(bin_dir.to_str().unwrap().to_string());
+            }
+        }
+    }
+
+    let mut conan_packages = Vec::new();
+    if let Some(conan_dir) = args.conan_dir.clone() {
+        let build_type = target_build_type(&args, &objdump_loc);
+        conan_packages = match conan::load(Path::new(&conan_dir), build_type == BuildType::Debug) {
+            Ok(packages) => packages,
+            Err(reason) => {
+                eprintln!("Invalid Conan dir: {reason}");
+                exit(14);
+            }
+        };
+        for package in conan_packages.iter().rev() {
+            for bin_dir in package.bin_dirs.iter().rev() {
+                if args.verbose {
+                    println!("Searching in bin dir \"{}\" of Conan package {}", bin_dir.display(), package.reference());
+                }
+                args.shallow_search_dir.insert(0, bin_dir.to_str().unwrap().to_string());
+            }
+        }
+    }
+
+    let mut vc_crt_dir = None;
+    if args.copy_vc_redist {
+        if let Some(redist_dir) = locate_vc_redist(&args) {
+            let runtime_dirs = vc_redist::runtime_dirs(&redist_dir, vc_redist_arch(&format));
+            if runtime_dirs.is_empty() {
+                eprintln!("No runtime dlls found in VC redist dir \"{}\"", redist_dir.display());
+                exit(16);
+            }
+            if args.verbose {
+                println!("Searching VC redistributable dlls in \"{}\"", redist_dir.display());
+            }
+            vc_crt_dir = vc_redist::crt_dir(&runtime_dirs).cloned();
+            for dir in runtime_dirs.iter().rev() {
+                args.shallow_search_dir.insert(0, dir.to_str().unwrap().to_string());
+            }
+        }
+    }
+
+    let mut ucrt_dir = None;
+    if args.app_local_ucrt {
+        let sdk_dir = args.windows_sdk_dir.as_ref().map(|d| std::env::current_dir().unwrap().join(d));
+        let dll_dir = match ucrt::dll_dir(sdk_dir.as_deref(), vc_redist_arch(&format)) {
+            Ok(dir) => dir,
+            Err(reason) => {
+                eprintln!("Can't deploy app-local Universal CRT: {reason}");
+                exit(17);
+            }
+        };
+        if args.verbose {
+            println!("Searching Universal CRT dlls in \"{}\"", dll_dir.display());
+        }
+        args.shallow_search_dir.insert(0, dll_dir.to_str().unwrap().to_string());
+        ucrt_dir = Some(dll_dir);
+    }
+
+    let python_prefix = args.python_prefix.as_ref().map(|prefix| std::env::current_dir().unwrap().join(prefix));
+    if let Some(prefix) = &python_prefix {
+        if !can_be_dir(prefix) {
+            eprintln!("Python prefix \"{}\" doesn't exist", prefix.display());
+            exit(20);
+        }
+        for dir in python::search_dirs(prefix).iter().rev() {
+            args.shallow_search_dir.insert(0, dir.to_str().unwrap().to_string());
+        }
+    }
+
+    let mut context=Context {
+        manifest: Manifest::load_or_default(&root_dir_path),
+        root_dir: root_dir_path.clone(),
+        lockfile: if args.locked { Some(Lockfile::load(&args.lockfile)) } else { None },
+        conan_packages,
+        build_type: match args.build_type {
+            BuildType::Auto => {
+                let target_name = Path::new(args.binary_file()).file_name().unwrap().to_str().unwrap();
+                build_type::build_type_of(target_name, &get_dependencies(args.binary_file(), &objdump_loc))
+            }
+            build_type => Some(build_type),
+        },
+        baseline: load_baseline(args.target_os, args.system_baseline.as_deref()),
+        ..Default::default()
+    };
+    if args.verbose {
+        if let Some(build_type) = context.build_type {
+            println!("Target is a {build_type:?} build, preferring {build_type:?} dlls");
+        }
+    }
+    if context.lockfile.is_some() {
+        check_locked(target_dir, &objdump_loc, &format, &args, &context);
+    }
+    if args.vc_redist_installer {
+        copy_vc_redist_installer(&args, &format, &mut context);
+    }
+    for binary_file in args.binary_files.clone() {
+        if !is_file(&binary_file) {
+            eprintln!("Given target \"{}\" is not a file",binary_file);
+            exit(5);
+        }
+        let target_format = get_file_format(&binary_file, &objdump_loc);
+        if target_format != format && args.tree.is_some() {
+            eprintln!("Warning: skip \"{binary_file}\" because it is {target_format}, but \"{}\" is {format}",
+                      args.binary_file());
+            continue;
+        }
+        if target_format != format {
+            eprintln!("Target \"{binary_file}\" is {target_format}, but \"{}\" is {format}, they can't share dlls",
+                      args.binary_file());
+            exit(5);
+        }
+        let binary_file = if args.copy_target {
+            stage_file(&binary_file, &binary_dir_path, &mut context)
+        } else {
+            binary_file
+        };
+        if let Some(subdir) = &args.dll_subdir {
+            check_subdir_layout(&binary_file, subdir, &objdump_loc, &context);
+        }
+        context.root_binaries.push(binary_file.clone());
+        if args.tree.is_some() && args.output_dir.is_none() {
+            // Put missing dlls next to the binary that requires them, unless a dll finds them next to the exes
+            context.app_dirs = if binary_file.to_lowercase().ends_with(".exe") {
+                Vec::new()
+            } else {
+                tree::app_dirs(&args.binary_files)
+            };
+            let binary_dir = Path::new(&binary_file).parent().unwrap().to_str().unwrap().to_string();
+            deploy_dll(&binary_file, &binary_dir, &objdump_loc, &format, &args,&mut context);
+        } else {
+            deploy_dll(&binary_file, target_dir, &objdump_loc, &format, &args,&mut context);
+        }
+    }
+    context.app_dirs.clear();
+
+    for dep in &args.optional_dll_abs_path() {
+
+        let mut dep_path=binary_dir_path.clone();
+        dep_path.push(dep);
+        let dep_path=dep_path.to_str().unwrap().to_string();
+        let dep_path=if args.copy_target {
+            stage_file(&dep_path, &binary_dir_path, &mut context)
+        } else {
+            dep_path
+        };
+        let dep_path=dep_path.as_str();
+        if args.verbose {
+            println!("Deploying for optional dll {dep_path}");
+        }
+        deploy_dll(dep_path,target_dir, &objdump_loc, &format, &args,&mut context);
+    }
+
+    if let Some(prefix) = &python_prefix {
+        deploy_python(prefix, &binary_dir_path, target_dir, &objdump_loc, &format, &args, &mut context);
+    }
+    if args.qt_plugins {
+        deploy_qt_plugins(target_dir, &objdump_loc, &format, &args, &mut context);
+    }
+    if !args.qml_dir.is_empty() || args.qml_imports.is_some() {
+        deploy_qml_modules(target_dir, &objdump_loc, &format, &args, &mut context);
+    }
+    if args.gtk {
+        deploy_gtk_runtime(target_dir, &objdump_loc, &format, &args, &mut context);
+    }
+    if args.scan_dll_strings {
+        deploy_dll_strings(target_dir, &objdump_loc, &format, &args, &mut context);
+    }
+    if let Some(crt_dir) = &vc_crt_dir {
+        copy_crt_set(crt_dir, target_dir, &objdump_loc, &format, &args, &mut context);
+    }
+    if let Some(dll_dir) = &ucrt_dir {
+        copy_ucrt_redist(dll_dir, target_dir, &objdump_loc, &format, &args, &mut context);
+    }
+
+    if let Some(subdir) = &args.dll_subdir {
+        let root_binaries = context.root_binaries.clone();
+        write_subdir_manifests(&root_binaries, &target_dir_path, subdir, &mut context);
+    }
+    if context.root_binaries.len() > 1 {
+        targets::print_report(&context.root_dir, &context.root_binaries, &context.loads);
+    }
+    if context.baseline.as_ref().is_some_and(|b| !b.has_ucrt()) && !args.app_local_ucrt
+        && context.imports.values().flatten().any(|dep| ucrt::is_ucrt_dll(dep)) {
+        eprintln!("Warning: the Universal CRT is not part of the target system, use --app-local-ucrt to deploy it");
+    }
+    build_type::check_mixed(context.build_type, &context.binary_build_types, args.deny_mixed_build_types);
+    build_type::warn_debug_crts(context.imports.values().flatten());
+    if let Some(lockfile) = &context.lockfile {
+        lockfile.verify_all_resolved(&context.resolved);
+    }
+    if args.vcpkg_root.is_some() {
+        vcpkg::print_report(&context.resolved, &context.vcpkg_owners);
+    }
+    if args.msys2_prefix.is_some() {
+        msys2::print_report(&context.resolved, &context.msys2_owners);
+    }
+    if args.conan_dir.is_some() {
+        conan::print_report(&context.conan_packages, &context.resolved);
+    }
+    if args.collect_licenses {
+        license::collect_licenses(&mut context);
+    }
+    if let Some(sbom) = &args.sbom {
+        sbom::write_cyclonedx(&context, sbom);
+    }
+    return context;
+}
+
+/// Exit if dlls in the subdir won't be found when `binary_file` is started
+fn check_subdir_layout(binary_file: &str, subdir: &str, objdump_file: &str, context: &Context) {
+    if let Err(reason) = dll_subdir::check_target(binary_file, &context.root_dir, subdir, objdump_file) {
+        eprintln!("Dlls in subdir \"{subdir}\" can't be loaded by \"{binary_file}\": {reason}");
+        exit(7);
+    }
+
+    let app_manifest = dll_subdir::application_manifest_location(binary_file);
+    let relative = manifest::relative_path(&context.root_dir, &app_manifest);
+    if is_file(&app_manifest) && !context.manifest.contains(&relative) {
+        eprintln!("Dlls in subdir \"{subdir}\" can't be loaded by \"{binary_file}\": \
+        \"{}\" already exists and was not generated by deploy-dll", app_manifest.display());
+        exit(7);
+    }
+}
+
+fn write_subdir_manifests(root_binaries: &[String], dll_dir: &Path, subdir: &str, context: &mut Context) {
+    let assembly_manifest = dll_subdir::write_assembly_manifest(dll_dir, subdir);
+    context.record_copy(assembly_manifest.to_str().unwrap(), manifest::GENERATED_SOURCE);
+    for binary in root_binaries {
+        let app_manifest = dll_subdir::write_application_manifest(binary, subdir);
+        println!("Generated \"{}\"", app_manifest.display());
+        context.record_copy(app_manifest.to_str().unwrap(), manifest::GENERATED_SOURCE);
+    }
+}
+
+/// Resolve dlls of the targets and check them against the lockfile before copying anything,
+/// so that a failed check doesn't leave a partially updated deployment.
+fn check_locked(target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args, context: &Context) {
+    let mut dry = Context {
+        manifest: Manifest::load_or_default(&context.root_dir),
+        root_dir: context.root_dir.clone(),
+        lockfile: Some(Lockfile::load(&args.lockfile)),
+        conan_packages: context.conan_packages.clone(),
+        build_type: context.build_type,
+        baseline: context.baseline.clone(),
+        dry_run: true,
+        ..Default::default()
+    };
+    for binary_file in &args.binary_files {
+        if !is_file(binary_file) || get_file_format(binary_file, objdump_file) != binary_format {
+            // Reported by the deployment itself
+            continue;
+        }
+        if args.tree.is_some() && args.output_dir.is_none() {
+            let binary_dir = Path::new(binary_file).parent().unwrap().to_str().unwrap().to_string();
+            deploy_dll(binary_file, &binary_dir, objdump_file, binary_format, args, &mut dry);
+        } else {
+            deploy_dll(binary_file, target_dir, objdump_file, binary_format, args, &mut dry);
+        }
+    }
+    for dep in &args.optional_dll_abs_path() {
+        let dep_path = args.binary_dir().join(dep);
+        if !is_file(&dep_path) {
+            continue;
+        }
+        deploy_dll(dep_path.to_str().unwrap(), target_dir, objdump_file, binary_format, args, &mut dry);
+    }
+}
+
+/// Copy `file` into the output dir, keeping its path relative to `binary_dir`. Returns the location of the copy.
+fn stage_file(file: &str, binary_dir: &Path, context: &mut Context) -> String {
+    let file_path = Path::new(file);
+    let relative = match file_path.strip_prefix(binary_dir) {
+        Ok(r) => r.to_path_buf(),
+        Err(_) => PathBuf::from(file_path.file_name().unwrap()),
+    };
+    let target_dir = context.root_dir.to_str().unwrap().to_string();
+    let dest = context.root_dir.join(&relative);
+    if dest == file_path {
+        return file.to_string();
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).expect("Failed to create directory in output dir");
+    }
+    let dest = dest.to_str().unwrap().to_string();
+    println!("Copying \"{file}\" to \"{target_dir}\"");
+    std::fs::copy(file, &dest).expect("Failed to copy target binary");
+    context.record_copy(&dest, file);
+    return dest;
+}
+
+/// The `VC/Redist/MSVC/<version>` dir given by --vc-redist-dir or found in Visual Studio
+fn locate_vc_redist(args: &Args) -> Option<PathBuf> {
+    if let Some(dir) = &args.vc_redist_dir {
+        if !can_be_dir(dir) {
+            eprintln!("VC redist dir \"{dir}\" doesn't exist");
+            exit(16);
+        }
+        return Some(std::env::current_dir().unwrap().join(dir));
+    }
+    let dir = vc_redist::locate_redist_dir();
+    if dir.is_none() {
+        eprintln!("Warning: VC redist dir of Visual Studio is not found, set VCToolsRedistDir or --vc-redist-dir");
+    }
+    return dir;
+}
+
+fn vc_redist_arch(binary_format: &str) -> &'static str {
+    let Some(arch) = vc_redist::arch_of(binary_format) else {
+        eprintln!("Microsoft runtime redistributables are not available for binary format \"{binary_format}\"");
+        exit(16);
+    };
+    return arch;
+}
+
+/// The Qt prefix given by --qt-prefix, or where Qt Core is found
+fn qt_prefix(args: &Args, context: &Context) -> PathBuf {
+    if let Some(prefix) = &args.qt_prefix {
+        return std::env::current_dir().unwrap().join(prefix);
+    }
+    let core = context.resolved.iter()
+        .find(|(name, _)| qt::module_of(name).as_deref() == Some("core"))
+        .and_then(|(_, dll)| qt::prefix_of(Path::new(&dll.source)));
+    let Some(prefix) = core else {
+        eprintln!("Can't tell the Qt prefix because Qt Core is not deployed, please set --qt-prefix");
+        exit(18);
+    };
+    return prefix;
+}
+
+/// Copy QML modules imported by --qml-dir sources and --qml-imports into `qml/`, and deploy dlls for their plugins
+fn deploy_qml_modules(target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args, context: &mut Context) {
+    let mut imports = BTreeSet::new();
+    for dir in &args.qml_dir {
+        if !can_be_dir(dir) {
+            eprintln!("QML dir \"{dir}\" doesn't exist");
+            exit(18);
+        }
+        imports.extend(qml::scan_dir(Path::new(dir)));
+    }
+    if let Some(file) = &args.qml_imports {
+        match qml::load_scanner_output(Path::new(file)) {
+            Ok(modules) => imports.extend(modules),
+            Err(reason) => {
+                eprintln!("{reason}");
+                exit(18);
+            }
+        }
+    }
+
+    let prefix = qt_prefix(args, context);
+    let Some(qml_dir) = qml::qml_dir(&prefix) else {
+        eprintln!("No qml dir found in Qt prefix \"{}\"", prefix.display());
+        exit(18);
+    };
+    let (modules, missing) = qml::resolve_modules(&qml_dir, &imports);
+    if !missing.is_empty() {
+        println!("QML modules not found in \"{}\", they are expected to be provided by the application: {}",
+                 qml_dir.display(), missing.iter().cloned().collect::<Vec<String>>().join(", "));
+    }
+
+    let debug = context.build_type == Some(BuildType::Debug);
+    let dest_root = context.root_dir.join("qml");
+    for (uri, dir) in &modules {
+        if args.verbose {
+            println!("Deploying QML module {uri} from \"{}\"", qml_dir.join(dir).display());
+        }
+        for file in qml::module_files(&qml_dir.join(dir), debug) {
+            let relative = file.strip_prefix(&qml_dir).unwrap();
+            let dest = dest_root.join(relative);
+            let dest_dir = dest.parent().unwrap();
+            std::fs::create_dir_all(dest_dir).expect("Failed to create QML module dir");
+            if file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dll")) {
+                let name = manifest::relative_path(&context.root_dir, &dest).to_lowercase();
+                let copy = copy_dll(&name, file.to_str().unwrap(), dest_dir.to_str().unwrap(), context);
+                deploy_dll(&copy, target_dir, objdump_file, binary_format, args, context);
+            } else {
+                copy_data_file(&file, &dest, context);
+            }
+        }
+    }
+    println!("Deployed {} QML modules into \"{}\"", modules.len(), dest_root.display());
+}
+
+/// Report dlls found by --scan-dll-strings, and deploy those that are found if --deploy-dll-strings.
+/// Deployed ones are scanned as well.
+fn deploy_dll_strings(target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args, context: &mut Context) {
+    let validate_arch = |loc: &Path| check_arch(loc, objdump_file, binary_format);
+    let mut statuses: BTreeMap<String, String> = BTreeMap::new();
+    loop {
+        let pending: Vec<String> = context.dll_strings.keys().filter(|name| !statuses.contains_key(*name)).cloned().collect();
+        if pending.is_empty() {
+            break;
+        }
+        for name in pending {
+            let status = if context.resolved.contains_key(&name) || is_file(&format!("{target_dir}/{name}")) {
+                "already deployed".to_string()
+            } else if let Some(location) = search_dll(&name, args, &validate_arch) {
+                if args.deploy_dll_strings {
+                    let copy = copy_dll(&name, &location, target_dir, context);
+                    deploy_dll(&copy, target_dir, objdump_file, binary_format, args, context);
+                    format!("deployed from \"{location}\"")
+                } else {
+                    format!("found at \"{location}\"")
+                }
+            } else {
+                "not found".to_string()
+            };
+            statuses.insert(name, status);
+        }
+    }
+
+    if context.dll_strings.is_empty() {
+        return;
+    }
+    println!("Possible runtime dependencies, found as dll names in strings of binaries:");
+    for (name, binaries) in &context.dll_strings {
+        println!("  {name} (in {}): {}", binaries.iter().cloned().collect::<Vec<String>>().join(", "), statuses[name]);
+    }
+    if !args.deploy_dll_strings && statuses.values().any(|status| status.starts_with("found")) {
+        println!("Use --deploy-dll-strings to deploy the ones that are found");
+    }
+}
+
+/// Deploy dlls for extension modules of the application, and copy the standard library of Python if --python-embed
+fn deploy_python(prefix: &Path, binary_dir: &Path, target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args, context: &mut Context) {
+    let mut modules = python::extension_modules(binary_dir);
+    modules.extend(python::extension_modules(&binary_dir.join("DLLs")));
+    for module in modules {
+        let module = module.to_str().unwrap().to_string();
+        let module = if args.copy_target {
+            stage_file(&module, binary_dir, context)
+        } else {
+            module
+        };
+        if args.verbose {
+            println!("Deploying for Python extension module {module}");
+        }
+        deploy_dll(&module, target_dir, objdump_file, binary_format, args, context);
+    }
+    if !args.python_embed {
+        return;
+    }
+
+    let Some(stem) = python::version_stem(prefix) else {
+        eprintln!("No python3XX.dll found in Python prefix \"{}\"", prefix.display());
+        exit(20);
+    };
+    let interpreter = format!("{stem}.dll");
+    if !context.resolved.contains_key(&interpreter) && !is_file(&format!("{target_dir}/{interpreter}")) {
+        // Loaded at runtime instead of imported
+        let copy = copy_dll(&interpreter, prefix.join(&interpreter).to_str().unwrap(), target_dir, context);
+        deploy_dll(&copy, target_dir, objdump_file, binary_format, args, context);
+    }
+
+    let stdlib = prefix.join(format!("{stem}.zip"));
+    if !is_file(&stdlib) {
+        eprintln!("\"{}\" doesn't exist, --python-embed requires the embeddable package of Python", stdlib.display());
+        exit(20);
+    }
+    let root_dir = context.root_dir.clone();
+    copy_data_file(&stdlib, &root_dir.join(stdlib.file_name().unwrap()), context);
+
+    let path_file = format!("{stem}._pth");
+    let dest = root_dir.join(&path_file);
+    if is_file(&prefix.join(&path_file)) {
+        copy_data_file(&prefix.join(&path_file), &dest, context);
+    } else {
+        let has_dlls_dir = !python::extension_modules(&root_dir.join("DLLs")).is_empty();
+        std::fs::write(&dest, python::default_path_file(&stem, has_dlls_dir)).expect("Failed to write ._pth file");
+        println!("Generated \"{}\"", dest.display());
+        context.record_copy(dest.to_str().unwrap(), manifest::GENERATED_SOURCE);
+    }
+
+    // The ._pth file puts the application dir into sys.path, where standard extension modules go
+    for module in python::stdlib_extension_modules(prefix) {
+        let name = module.file_name().unwrap().to_str().unwrap().to_lowercase();
+        let copy = copy_dll(&name, module.to_str().unwrap(), root_dir.to_str().unwrap(), context);
+        deploy_dll(&copy, target_dir, objdump_file, binary_format, args, context);
+    }
+}
+
+/// Copy a runtime file that is not a binary, and record it
+fn copy_data_file(source: &Path, dest: &Path, context: &mut Context) {
+    std::fs::create_dir_all(dest.parent().unwrap()).expect("Failed to create dir for data file");
+    std::fs::copy(source, dest).unwrap_or_else(|e| panic!("Failed to copy \"{}\": {e}", source.display()));
+    context.record_copy(dest.to_str().unwrap(), source.to_str().unwrap());
+    context.data_files.insert(dest.to_path_buf());
+}
+
+/// Copy gdk-pixbuf loaders, GSettings schemas and icon themes of GTK. Loaders are deployed like optional dlls,
+/// and their cache is rewritten to relative paths.
+fn deploy_gtk_runtime(target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args, context: &mut Context) {
+    let prefix = match &args.gtk_prefix {
+        Some(prefix) => std::env::current_dir().unwrap().join(prefix),
+        None => {
+            let glib = context.resolved.iter()
+                .find(|(name, _)| gtk::is_prefix_dll(name))
+                .and_then(|(_, dll)| Path::new(&dll.source).parent().map(gtk::data_prefix));
+            let Some(prefix) = glib else {
+                eprintln!("Can't tell the GTK prefix because GLib is not deployed, please set --gtk-prefix");
+                exit(19);
+            };
+            prefix
+        }
+    };
+    if args.verbose {
+        println!("Deploying GTK runtime files from \"{}\"", prefix.display());
+    }
+    let data_prefix = gtk::data_prefix(Path::new(target_dir));
+
+    let loaders = gtk::loaders(&prefix);
+    let loaders_dir = data_prefix.join(gtk::LOADERS_DIR);
+    let mut deployed_loaders = BTreeSet::new();
+    for loader in &loaders {
+        std::fs::create_dir_all(&loaders_dir).expect("Failed to create gdk-pixbuf loaders dir");
+        let filename = loader.file_name().unwrap().to_str().unwrap();
+        let name = manifest::relative_path(&context.root_dir, &loaders_dir.join(filename)).to_lowercase();
+        let copy = copy_dll(&name, loader.to_str().unwrap(), loaders_dir.to_str().unwrap(), context);
+        deploy_dll(&copy, target_dir, objdump_file, binary_format, args, context);
+        deployed_loaders.insert(filename.to_lowercase());
+    }
+    if !loaders.is_empty() {
+        let cache = prefix.join(gtk::LOADERS_CACHE);
+        match std::fs::read_to_string(&cache) {
+            Ok(content) => {
+                let dest = data_prefix.join(gtk::LOADERS_CACHE);
+                std::fs::write(&dest, gtk::rewrite_loaders_cache(&content, &deployed_loaders))
+                    .expect("Failed to write loaders.cache");
+                println!("Generated \"{}\"", dest.display());
+                context.record_copy(dest.to_str().unwrap(), manifest::GENERATED_SOURCE);
+            }
+            Err(_) => eprintln!("Warning: \"{}\" doesn't exist, run gdk-pixbuf-query-loaders --update-cache to generate it",
+                                cache.display()),
+        }
+    }
+
+    let schemas = prefix.join(gtk::COMPILED_SCHEMAS);
+    if is_file(&schemas) {
+        copy_data_file(&schemas, &data_prefix.join(gtk::COMPILED_SCHEMAS), context);
+    } else {
+        eprintln!("Warning: \"{}\" doesn't exist, run glib-compile-schemas to generate it", schemas.display());
+    }
+
+    for theme in gtk::ICON_THEMES {
+        let relative = format!("share/icons/{theme}");
+        let theme_dir = prefix.join(&relative);
+        if !can_be_dir(&theme_dir) {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(&theme_dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() {
+                let dest = data_prefix.join(&relative).join(entry.path().strip_prefix(&theme_dir).unwrap());
+                copy_data_file(entry.path(), &dest, context);
+            }
+        }
+    }
+    println!("Deployed {} gdk-pixbuf loaders, GSettings schemas and icon themes into \"{}\"",
+             loaders.len(), data_prefix.display());
+}
+
+/// Copy the plugins of imported Qt modules, and deploy dlls for them
+fn deploy_qt_plugins(target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args, context: &mut Context) {
+    let modules: BTreeSet<String> = context.resolved.keys().filter_map(|name| qt::module_of(name)).collect();
+    if modules.is_empty() {
+        println!("No Qt module is imported, skip deploying Qt plugins");
+        return;
+    }
+    let prefix = qt_prefix(args, context);
+    let Some(plugins_dir) = qt::plugins_dir(&prefix) else {
+        eprintln!("No plugins dir found in Qt prefix \"{}\"", prefix.display());
+        exit(18);
+    };
+    if args.verbose {
+        println!("Deploying Qt plugins from \"{}\" for modules {:?}", plugins_dir.display(), modules);
+    }
+
+    let debug = context.build_type == Some(BuildType::Debug);
+    let dest_root = match &args.qt_plugin_dir {
+        Some(dir) => context.root_dir.join(dir),
+        None => context.root_dir.clone(),
+    };
+    for plugin_type in qt::plugin_types(&modules) {
+        let dest_dir = dest_root.join(plugin_type);
+        for plugin in qt::plugins(&plugins_dir, plugin_type, debug) {
+            std::fs::create_dir_all(&dest_dir).expect("Failed to create Qt plugin dir");
+            // Keyed by the path in deploy dir, like platforms/qwindows.dll
+            let name = manifest::relative_path(&context.root_dir, &dest_dir.join(plugin.file_name().unwrap())).to_lowercase();
+            let copy = copy_dll(&name, plugin.to_str().unwrap(), dest_dir.to_str().unwrap(), context);
+            deploy_dll(&copy, target_dir, objdump_file, binary_format, args, context);
+        }
+    }
+
+    if let Some(plugin_dir) = &args.qt_plugin_dir {
+        let qt_conf = context.root_dir.join("qt.conf");
+        if is_file(&qt_conf) && !context.manifest.contains("qt.conf") {
+            eprintln!("Warning: \"{}\" is not generated by deploy-dll, it is kept. Make sure that it sets Plugins = {plugin_dir}",
+                      qt_conf.display());
+            return;
+        }
+        std::fs::write(&qt_conf, qt::qt_conf(plugin_dir)).expect("Failed to write qt.conf");
+        println!("Generated \"{}\"", qt_conf.display());
+        context.record_copy(qt_conf.to_str().unwrap(), manifest::GENERATED_SOURCE);
+    }
+}
+
+/// Copy the rest of the CRT if any dll of it is deployed, so that all of them come from the same version
+fn copy_crt_set(crt_dir: &Path, target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args, context: &mut Context) {
+    if !context.resolved.values().any(|r| Path::new(&r.source).starts_with(crt_dir)) {
+        return;
+    }
+    for dll in vc_redist::crt_set(crt_dir) {
+        let name = dll.file_name().unwrap().to_str().unwrap().to_lowercase();
+        if context.resolved.contains_key(&name) || is_file(&format!("{target_dir}/{name}")) {
+            continue;
+        }
+        let copy = copy_dll(&name, dll.to_str().unwrap(), target_dir, context);
+        deploy_dll(&copy, target_dir, objdump_file, binary_format, args, context);
+    }
+}
+
+/// The api-ms-win-crt-* dlls only forward to ucrtbase.dll, and import api-ms-win-core-* forwarders,
+/// none of which show up as imports. Once any of them is deployed, copy the whole redistributable.
+fn copy_ucrt_redist(dll_dir: &Path, target_dir: &str, objdump_file: &str, binary_format: &str, args: &Args, context: &mut Context) {
+    if !context.resolved.keys().any(|name| ucrt::is_ucrt_dll(name)) {
+        return;
+    }
+    for dll in ucrt::redist_dlls(dll_dir) {
+        let name = dll.file_name().unwrap().to_str().unwrap().to_lowercase();
+        if context.resolved.contains_key(&name) || is_file(&format!("{target_dir}/{name}")) {
+            continue;
+        }
+        let copy = copy_dll(&name, dll.to_str().unwrap(), target_dir, context);
+        deploy_dll(&copy, target_dir, objdump_file, binary_format, args, context);
+    }
+}
+
+/// Copy `vc_redist.<arch>.exe` next to the target binaries
+fn copy_vc_redist_installer(args: &Args, binary_format: &str, context: &mut Context) {
+    let Some(redist_dir) = locate_vc_redist(args) else {
+        exit(16);
+    };
+    let installer = vc_redist::installer(&redist_dir, vc_redist_arch(binary_format));
+    if !is_file(&installer) {
+        eprintln!("VC redistributable installer \"{}\" doesn't exist", installer.display());
+        exit(16);
+    }
+    let dest = context.root_dir.join(installer.file_name().unwrap());
+    println!("Copying \"{}\" to \"{}\"", installer.display(), context.root_dir.display());
+    std::fs::copy(&installer, &dest).expect("Failed to copy VC redistributable installer");
+    context.record_copy(dest.to_str().unwrap(), installer.to_str().unwrap());
+    // Shipped with the dlls, so that it is packed as well
+    context.deployed_dlls.insert(dest);
+}
+
+/// Build type given by --build-type, or deduced from the imports of target binary
+fn target_build_type(args: &Args, objdump_file: &str) -> BuildType {
+    return match args.build_type {
+        BuildType::Auto => build_type::detect(&get_dependencies(args.binary_file(), objdump_file)),
+        build_type => build_type,
+    };
+}
+
+/// The bin dir of vcpkg installed tree that matches the triplet and build type
+fn vcpkg_bin_dir(vcpkg_root: &str, triplet: Option<&str>, binary_format: &str, build_type: BuildType) -> PathBuf {
+    let vcpkg_root_path = std::env::current_dir().unwrap().join(vcpkg_root);
+    let Some(installed_dir) = vcpkg::installed_dir(&vcpkg_root_path) else {
+        eprintln!("\"{vcpkg_root}\" is neither a vcpkg root nor a vcpkg installed dir");
+        exit(12);
+    };
+    let Some(triplet) = triplet.or_else(|| vcpkg::default_triplet(binary_format)) else {
+        eprintln!("Can't deduce vcpkg triplet for binary format \"{binary_format}\", please set --vcpkg-triplet");
+        exit(12);
+    };
+    let bin_dir = vcpkg::bin_dir(&installed_dir, triplet, build_type == BuildType::Debug);
+    if !can_be_dir(&bin_dir) {
+        eprintln!("vcpkg bin dir \"{}\" doesn't exist, is triplet {triplet} installed?", bin_dir.display());
+        exit(12);
+    }
+    return bin_dir;
+}
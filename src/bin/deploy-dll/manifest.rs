@@ -0,0 +1,264 @@
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::process::exit;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Name of the manifest file written into the deploy directory
+pub const MANIFEST_FILENAME: &str = ".deploy-dll.json";
+
+const MANIFEST_VERSION: u32 = 1;
+
+/// Source of files that are generated by deploy-dll rather than copied
+pub const GENERATED_SOURCE: &str = "[generated]";
+
+/// Records every file that deploy-dll copied into a directory, so that they can be removed later.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    version: u32,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestEntry {
+    /// Path of the deployed file, relative to the directory of manifest
+    pub path: String,
+    /// Where the file was copied from
+    pub source: String,
+    /// SHA-256 of the file when it was deployed
+    pub sha256: String,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        return Manifest {
+            version: MANIFEST_VERSION,
+            files: Vec::new(),
+        };
+    }
+}
+
+impl Manifest {
+    pub fn location(dir: &Path) -> PathBuf {
+        let mut loc = dir.to_path_buf();
+        loc.push(MANIFEST_FILENAME);
+        return loc;
+    }
+
+    /// Load manifest in `dir`. Returns `None` if there is no manifest.
+    pub fn load(dir: &Path) -> Option<Manifest> {
+        let loc = Self::location(dir);
+        let content = std::fs::read_to_string(&loc).ok()?;
+        return match serde_json::from_str::<Manifest>(&content) {
+            Ok(m) => Some(m),
+            Err(e) => {
+                eprintln!("Failed to parse deployment manifest \"{}\" because {e}", loc.display());
+                exit(6);
+            }
+        };
+    }
+
+    /// Load manifest in `dir`, and forget files that no longer exist.
+    pub fn load_or_default(dir: &Path) -> Manifest {
+        let mut manifest = Self::load(dir).unwrap_or_default();
+        manifest.files.retain(|entry| crate::is_file(&dir.join(&entry.path)));
+        return manifest;
+    }
+
+    pub fn save(&self, dir: &Path) {
+        let loc = Self::location(dir);
+        let content = serde_json::to_string_pretty(self).expect("Serialize deployment manifest");
+        if let Err(e) = std::fs::write(&loc, content) {
+            eprintln!("Failed to write deployment manifest \"{}\" because {e}", loc.display());
+            exit(6);
+        }
+    }
+
+    /// Every deployed file
+    pub fn files(&self) -> impl Iterator<Item = &ManifestEntry> {
+        return self.files.iter();
+    }
+
+    /// Files generated by deploy-dll, rather than copied
+    pub fn generated_files(&self) -> impl Iterator<Item = &ManifestEntry> {
+        return self.files.iter().filter(|e| e.source == GENERATED_SOURCE);
+    }
+
+    /// Deployed files under directory `dir`, which is relative to the manifest
+    pub fn files_in<'a>(&'a self, dir: &'a str) -> impl Iterator<Item = &'a ManifestEntry> {
+        return self.files.iter().filter(move |e| Path::new(&e.path).starts_with(dir));
+    }
+
+    /// Whether the file at `path` relative to the manifest was deployed
+    pub fn contains(&self, path: &str) -> bool {
+        return self.entry(path).is_some();
+    }
+
+    pub fn entry(&self, path: &str) -> Option<&ManifestEntry> {
+        return self.files.iter().find(|e| e.path == path);
+    }
+
+    /// The deployed file named `name` in any directory, ignoring case
+    pub fn entry_named(&self, name: &str) -> Option<&ManifestEntry> {
+        return self.files.iter()
+            .find(|e| Path::new(&e.path).file_name().and_then(|n| n.to_str()).is_some_and(|n| n.eq_ignore_ascii_case(name)));
+    }
+
+    /// Record that `file` in `dir` was copied from `source`. Returns SHA-256 of `file`.
+    pub fn record(&mut self, dir: &Path, file: &Path, source: &str) -> String {
+        let path = relative_path(dir, file);
+        let entry = ManifestEntry {
+            path: path.clone(),
+            source: source.replace('\\', "/"),
+            sha256: file_sha256(file),
+        };
+
+        let sha256 = entry.sha256.clone();
+        if let Some(old) = self.files.iter_mut().find(|e| e.path == path) {
+            *old = entry;
+        } else {
+            self.files.push(entry);
+        }
+        return sha256;
+    }
+}
+
+/// Path of `file` relative to `dir`, with `/` as separator. Files outside `dir`, like the GTK data
+/// in `lib` and `share` next to `bin`, get `..`, so that the deployment can still be moved as a whole.
+pub fn relative_path(dir: &Path, file: &Path) -> String {
+    return relative_path_from(dir, file).unwrap_or_else(|| file.to_str().unwrap().replace('\\', "/"));
+}
+
+/// Absolute form of `path`, resolving `.` and `..` without touching the file system.
+pub fn absolute_path(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().expect("Failed to get current dir").join(path)
+    };
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(result.components().next_back(), Some(Component::Normal(_))) {
+                    result.pop();
+                }
+            }
+            c => result.push(c),
+        }
+    }
+    return result;
+}
+
+/// Path of `file` relative to `dir` with `/` as separator, going up with `..` if needed.
+/// Returns `None` if they don't share a root, like on different drives.
+pub fn relative_path_from(dir: &Path, file: &Path) -> Option<String> {
+    let dir = absolute_path(dir);
+    let file = absolute_path(file);
+    let dir: Vec<Component> = dir.components().collect();
+    let file: Vec<Component> = file.components().collect();
+    if dir.first() != file.first() {
+        return None;
+    }
+    let common = dir.iter().zip(file.iter()).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); dir.len() - common];
+    for c in &file[common..] {
+        parts.push(c.as_os_str().to_str().unwrap().to_string());
+    }
+    if parts.is_empty() {
+        return Some(".".to_string());
+    }
+    return Some(parts.join("/"));
+}
+
+/// The deepest dir that contains both `a` and `b`
+pub fn common_ancestor(a: &Path, b: &Path) -> PathBuf {
+    let a = absolute_path(a);
+    let b = absolute_path(b);
+    return a.components().zip(b.components())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x)
+        .collect();
+}
+
+/// Compute SHA-256 of a file, in lower-case hex.
+pub fn file_sha256(file: &Path) -> String {
+    let mut f = std::fs::File::open(file)
+        .unwrap_or_else(|e| panic!("Failed to open \"{}\" for hashing: {e}", file.display()));
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = f.read(&mut buffer)
+            .unwrap_or_else(|e| panic!("Failed to read \"{}\" for hashing: {e}", file.display()));
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    return format!("{:x}", hasher.finalize());
+}
+
+/// Remove files listed in the manifest of `dir`, skipping those that were modified after deployment.
+pub fn clean(dir: &str, verbose: bool) {
+    let dir = PathBuf::from(dir);
+    let mut manifest = match Manifest::load(&dir) {
+        Some(m) => m,
+        None => {
+            eprintln!("No deployment manifest found in \"{}\"", dir.display());
+            exit(6);
+        }
+    };
+
+    let mut kept = Vec::new();
+    for entry in manifest.files.drain(..) {
+        let file = absolute_path(&dir.join(&entry.path));
+        if !crate::is_file(&file) {
+            if verbose {
+                println!("\"{}\" is already removed", file.display());
+            }
+            continue;
+        }
+        if file_sha256(&file) != entry.sha256 {
+            println!("Keep \"{}\" because it was modified after deployment", file.display());
+            kept.push(entry);
+            continue;
+        }
+
+        println!("Removing \"{}\"", file.display());
+        if let Err(e) = std::fs::remove_file(&file) {
+            eprintln!("Failed to remove \"{}\" because {e}", file.display());
+            kept.push(entry);
+            continue;
+        }
+        remove_empty_parents(&dir, &file);
+    }
+
+    if kept.is_empty() {
+        let loc = Manifest::location(&dir);
+        if let Err(e) = std::fs::remove_file(&loc) {
+            eprintln!("Failed to remove deployment manifest \"{}\" because {e}", loc.display());
+            exit(6);
+        }
+    } else {
+        manifest.files = kept;
+        manifest.save(&dir);
+    }
+}
+
+/// Remove empty directories between `file` and `root`, `root` itself is kept.
+/// For a file outside of `root`, it stops at the dir that contains both.
+fn remove_empty_parents(root: &Path, file: &Path) {
+    let root = &common_ancestor(root, file);
+    let mut current = file.parent();
+    while let Some(dir) = current {
+        if dir == root || !dir.starts_with(root) {
+            break;
+        }
+        // remove_dir fails on non-empty directories, which is what stops the loop
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
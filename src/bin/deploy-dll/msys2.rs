@@ -0,0 +1,193 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use crate::lock::LockedDll;
+
+/// Environments of MSYS2, each has its own prefix like `C:/msys64/ucrt64`
+pub const ENVIRONMENTS: [&str; 6] = ["mingw64", "mingw32", "ucrt64", "clang64", "clang32", "clangarm64"];
+
+/// The pacman package that installed a file in an MSYS2 installation
+#[derive(Debug, Clone)]
+pub struct PacmanOwner {
+    pub package: String,
+    pub version: String,
+    /// Root of the MSYS2 installation, like `C:/msys64`
+    pub root: PathBuf,
+    /// Files installed by the package, relative to `root`
+    pub files: Vec<String>,
+}
+
+/// Find the MSYS2 root that contains `file`, which has `var/lib/pacman/local`
+pub fn root_of(file: &Path) -> Option<PathBuf> {
+    for dir in file.ancestors().skip(1) {
+        if crate::can_be_dir(&pacman_local_db(dir)) {
+            return Some(dir.to_path_buf());
+        }
+    }
+    return None;
+}
+
+fn pacman_local_db(root: &Path) -> PathBuf {
+    let mut db = root.to_path_buf();
+    db.push("var/lib/pacman/local");
+    return db;
+}
+
+/// Split `<name>-<pkgver>-<pkgrel>` of a local db entry
+fn parse_entry_name(entry: &str) -> Option<(String, String)> {
+    let (rest, pkgrel) = entry.rsplit_once('-')?;
+    let (name, pkgver) = rest.rsplit_once('-')?;
+    return Some((name.to_string(), format!("{pkgver}-{pkgrel}")));
+}
+
+/// Parse the `%FILES%` section of a `files` entry in pacman local db
+fn parse_files(content: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut in_files = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('%') {
+            in_files = line == "%FILES%";
+            continue;
+        }
+        if in_files && !line.is_empty() && !line.ends_with('/') {
+            files.push(line.to_string());
+        }
+    }
+    return files;
+}
+
+/// Packages in pacman local db by the files they own. Each MSYS2 root is indexed once, when it's first used.
+#[derive(Default)]
+pub struct OwnerIndex {
+    roots: RefCell<BTreeMap<PathBuf, RootIndex>>,
+}
+
+#[derive(Default)]
+struct RootIndex {
+    owners: Vec<PacmanOwner>,
+    /// Lower-case path relative to the root -> index in `owners`
+    by_file: BTreeMap<String, usize>,
+}
+
+impl OwnerIndex {
+    /// Find the package that owns `file`.
+    pub fn find_owner(&self, file: &Path) -> Option<PacmanOwner> {
+        let root = root_of(file)?;
+        let relative = file.strip_prefix(&root).ok()?.to_str()?.replace('\\', "/").to_lowercase();
+        let mut roots = self.roots.borrow_mut();
+        let index = roots.entry(root.clone()).or_insert_with(|| index_root(&root));
+        let owner = index.by_file.get(&relative)?;
+        return Some(index.owners[*owner].clone());
+    }
+}
+
+/// Read every `files` entry of the pacman local db of `root`
+fn index_root(root: &Path) -> RootIndex {
+    let mut index = RootIndex::default();
+    let Ok(read_dir) = std::fs::read_dir(pacman_local_db(root)) else {
+        return index;
+    };
+    let mut entries: Vec<PathBuf> = read_dir
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    entries.sort();
+
+    for entry in entries {
+        let Ok(content) = std::fs::read_to_string(entry.join("files")) else {
+            continue;
+        };
+        let Some((package, version)) = entry.file_name().and_then(|n| n.to_str()).and_then(parse_entry_name) else {
+            continue;
+        };
+        let files = parse_files(&content);
+        for file in &files {
+            // The first package wins, like the first match of a search in sorted order
+            index.by_file.entry(file.to_lowercase()).or_insert(index.owners.len());
+        }
+        index.owners.push(PacmanOwner {
+            package,
+            version,
+            root: root.to_path_buf(),
+            files,
+        });
+    }
+    return index;
+}
+
+/// The MSYS2 environment that builds binaries of `binary_format` with these imports
+fn deduce_environment(binary_format: &str, imports: &[String]) -> &'static str {
+    if let Ok(msystem) = std::env::var("MSYSTEM") {
+        let msystem = msystem.to_lowercase();
+        if let Some(env) = ENVIRONMENTS.iter().find(|env| **env == msystem) {
+            return env;
+        }
+    }
+    let imports_any = |names: &[&str]| imports.iter().any(|i| names.contains(&i.as_str()));
+    return match binary_format {
+        "pei-i386" => if imports_any(&["libc++.dll", "libunwind.dll"]) { "clang32" } else { "mingw32" },
+        "pei-aarch64-little" => "clangarm64",
+        _ => {
+            if imports_any(&["libc++.dll", "libunwind.dll"]) {
+                "clang64"
+            } else if imports_any(&["msvcrt.dll"]) {
+                "mingw64"
+            } else {
+                "ucrt64"
+            }
+        }
+    };
+}
+
+/// The bin dir to search in. `prefix` is either an environment prefix like `C:/msys64/ucrt64`,
+/// or the root of MSYS2, whose environment is deduced from target binary.
+pub fn bin_dir(prefix: &Path, binary_format: &str, imports: &[String]) -> Result<PathBuf, String> {
+    let prefix_name = prefix.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_lowercase();
+    let env_prefix = if ENVIRONMENTS.contains(&prefix_name.as_str()) {
+        prefix.to_path_buf()
+    } else if crate::can_be_dir(&pacman_local_db(prefix)) {
+        prefix.join(deduce_environment(binary_format, imports))
+    } else {
+        return Err(format!("\"{}\" is neither an MSYS2 root nor an environment prefix like ucrt64", prefix.display()));
+    };
+
+    let bin = env_prefix.join("bin");
+    if !crate::can_be_dir(&bin) {
+        return Err(format!("\"{}\" doesn't exist, is this environment installed?", bin.display()));
+    }
+    return Ok(bin);
+}
+
+/// The environment that `file` in `<env>/bin` belongs to, like ucrt64
+fn environment_of(file: &Path) -> Option<String> {
+    let bin = file.parent()?;
+    if !bin.file_name()?.to_str()?.eq_ignore_ascii_case("bin") {
+        return None;
+    }
+    let env = bin.parent()?.file_name()?.to_str()?.to_lowercase();
+    return ENVIRONMENTS.contains(&env.as_str()).then_some(env);
+}
+
+/// Print the package that owns each resolved dll, and warn if dlls come from different environments.
+pub fn print_report(resolved: &BTreeMap<String, LockedDll>, owners: &OwnerIndex) {
+    let mut environments: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    println!("Dlls provided by MSYS2 packages:");
+    for (name, dll) in resolved {
+        let source = Path::new(&dll.source);
+        if let Some(env) = environment_of(source) {
+            environments.entry(env).or_default().insert(name.clone());
+        }
+        if let Some(owner) = owners.find_owner(source) {
+            println!("  {name} <- {} {}", owner.package, owner.version);
+        }
+    }
+
+    if environments.len() > 1 {
+        eprintln!("Warning: dlls from different MSYS2 environments are mixed, they are not ABI compatible:");
+        for (env, dlls) in &environments {
+            eprintln!("  {env}: {}", dlls.iter().cloned().collect::<Vec<String>>().join(", "));
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, exit};
+use clap::ValueEnum;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
+use crate::Context;
+use crate::manifest;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ArchiveFormat {
+    Zip,
+    #[value(name = "7z")]
+    SevenZip,
+}
+
+/// Collect files to pack, keyed by their path in the archive. Keys are sorted, which keeps the archive reproducible.
+/// The archive is rooted at the dir that contains all of them, which is the deploy dir unless some files are
+/// outside of it, like the GTK data in `lib` and `share` next to `bin`. Returns the archive root and the files.
+pub fn collect_files(context: &Context, include_dirs: &[String]) -> (PathBuf, BTreeMap<String, PathBuf>) {
+    let root = &context.root_dir;
+    let mut paths = Vec::new();
+    let mut add = |file: &Path| {
+        paths.push(manifest::absolute_path(file));
+    };
+
+    for file in context.deployed_dlls.iter().chain(&context.data_files) {
+        add(file);
+    }
+    for entry in context.manifest.generated_files() {
+        add(&root.join(&entry.path));
+    }
+    for entry in context.manifest.files_in(crate::license::LICENSE_DIR) {
+        add(&root.join(&entry.path));
+    }
+
+    for dir in include_dirs {
+        let dir = root.join(dir);
+        if !crate::can_be_dir(&dir) {
+            eprintln!("Included dir \"{}\" doesn't exist", dir.display());
+            exit(9);
+        }
+        for entry in walkdir::WalkDir::new(&dir) {
+            let entry = entry.unwrap_or_else(|e| panic!("Failed to walk in \"{}\": {e}", dir.display()));
+            if entry.file_type().is_file() {
+                add(entry.path());
+            }
+        }
+    }
+
+    let archive_root = paths.iter().fold(manifest::absolute_path(root), |base, file| manifest::common_ancestor(&base, file));
+    let files = paths.into_iter()
+        .map(|file| (manifest::relative_path(&archive_root, &file), file))
+        .collect();
+    return (archive_root, files);
+}
+
+/// Pack `files` into `output`, and write its SHA-256 into `<output>.sha256`.
+pub fn pack(files: &BTreeMap<String, PathBuf>, root: &Path, format: ArchiveFormat, output: &str, verbose: bool) {
+    let output = Path::new(output);
+    if let Some(parent) = output.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).expect("Failed to create directory for archive");
+        }
+    }
+    if crate::is_file(&output) {
+        std::fs::remove_file(output).expect("Failed to remove old archive");
+    }
+
+    if verbose {
+        for name in files.keys() {
+            println!("Packing \"{name}\"");
+        }
+    }
+    match format {
+        ArchiveFormat::Zip => write_zip(files, output),
+        ArchiveFormat::SevenZip => write_7z(files, root, output),
+    }
+
+    let hash = manifest::file_sha256(output);
+    let hash_file = format!("{}.sha256", output.display());
+    let filename = output.file_name().unwrap().to_str().unwrap();
+    std::fs::write(&hash_file, format!("{hash}  {filename}\n")).expect("Failed to write archive hash");
+    println!("Packed {} files into \"{}\", sha256: {hash}", files.len(), output.display());
+}
+
+fn write_zip(files: &BTreeMap<String, PathBuf>, output: &Path) {
+    let file = std::fs::File::create(output)
+        .unwrap_or_else(|e| panic!("Failed to create \"{}\": {e}", output.display()));
+    let mut zip = ZipWriter::new(file);
+    // Fixed timestamp and permissions, so that the archive only depends on file contents
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .compression_level(Some(6))
+        .last_modified_time(DateTime::default())
+        .unix_permissions(0o644);
+
+    for (name, path) in files {
+        let content = std::fs::read(path)
+            .unwrap_or_else(|e| panic!("Failed to read \"{}\": {e}", path.display()));
+        zip.start_file(name.as_str(), options).expect("Failed to add file to zip");
+        zip.write_all(&content).expect("Failed to write zip");
+    }
+    zip.finish().expect("Failed to finish zip");
+}
+
+fn write_7z(files: &BTreeMap<String, PathBuf>, root: &Path, output: &Path) {
+    for (name, path) in files {
+        if root.join(name) != *path {
+            eprintln!("\"{}\" is not in \"{}\", it can't be packed into 7z",
+                      path.display(), root.display());
+            exit(9);
+        }
+    }
+
+    // 7z reads the file list from a list file, in the given order
+    let list_file = output.with_extension("7z.list");
+    let list: Vec<&str> = files.keys().map(|k| k.as_str()).collect();
+    std::fs::write(&list_file, list.join("\n")).expect("Failed to write 7z list file");
+
+    let output_abs = if output.is_absolute() {
+        output.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap().join(output)
+    };
+    let status = Command::new("7z")
+        .args(["a", "-t7z", "-mtm=off", "-mtc=off", "-mta=off", "-mqs=off", "-bd"])
+        .arg(&output_abs)
+        .arg(format!("@{}", list_file.canonicalize().unwrap().display()))
+        .current_dir(root)
+        .status();
+    let _ = std::fs::remove_file(&list_file);
+    match status {
+        Ok(s) if s.success() => {}
+        Ok(s) => {
+            eprintln!("7z failed with error code {s}");
+            exit(9);
+        }
+        Err(e) => {
+            eprintln!("Failed to run 7z because {e}");
+            exit(9);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+/// Where a dll comes from, deduced from its source location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    /// Package manager or distribution, for example vcpkg, msys2 or qt
+    pub ecosystem: String,
+    /// Package name, if known
+    pub name: Option<String>,
+    /// Package version, if known
+    pub version: Option<String>,
+    /// Extra information, like the vcpkg triplet or MSYS2 environment
+    pub detail: Option<String>,
+}
+
+/// Guess the package of a dll from where it was found.
+pub fn detect_package(source: &str, vcpkg_owners: &crate::vcpkg::OwnerIndex, msys2_owners: &crate::msys2::OwnerIndex)
+                      -> Option<Package> {
+    let source_path = std::path::Path::new(source);
+    if let Some(owner) = vcpkg_owners.find_owner(source_path) {
+        return Some(Package {
+            ecosystem: "vcpkg".to_string(),
+            name: Some(owner.port),
+            version: Some(owner.version),
+            detail: Some(owner.triplet),
+        });
+    }
+    if let Some(owner) = msys2_owners.find_owner(source_path) {
+        let env = source.replace('\\', "/").strip_prefix(owner.root.to_str()?.replace('\\', "/").as_str())
+            .and_then(|rel| rel.trim_start_matches('/').split('/').next().map(|s| s.to_string()));
+        return Some(Package {
+            ecosystem: "msys2".to_string(),
+            name: Some(owner.package),
+            version: Some(owner.version),
+            detail: env,
+        });
+    }
+
+    // The owner can't be found, guess from the location
+    let original_filename = source_path.file_name()?.to_str()?.to_string();
+    let source = source.replace('\\', "/").to_lowercase();
+    let components: Vec<&str> = source.split('/').collect();
+    let dir_components = &components[..components.len().saturating_sub(1)];
+    let filename = components.last().copied().unwrap_or_default();
+
+    // <vcpkg>/installed/<triplet>/bin, <vcpkg>/installed/<triplet>/debug/bin, or <build>/vcpkg_installed/<triplet>/bin
+    if let Some(idx) = dir_components.iter().position(|c| *c == "installed" || *c == "vcpkg_installed") {
+        if let Some(triplet) = dir_components.get(idx + 1) {
+            if dir_components.last() == Some(&"bin") && source.contains("vcpkg") {
+                return Some(Package {
+                    ecosystem: "vcpkg".to_string(),
+                    name: None,
+                    version: None,
+                    detail: Some(triplet.to_string()),
+                });
+            }
+        }
+    }
+
+    // <msys64>/<environment>/bin
+    if dir_components.len() >= 2 && dir_components.last() == Some(&"bin") {
+        let env = dir_components[dir_components.len() - 2];
+        if crate::msys2::ENVIRONMENTS.contains(&env) {
+            return Some(Package {
+                ecosystem: "msys2".to_string(),
+                name: None,
+                version: None,
+                detail: Some(env.to_string()),
+            });
+        }
+    }
+
+    // Official Qt installs look like <root>/Qt/<version>/<kit>/bin
+    let qt_version = dir_components.iter().position(|c| *c == "qt")
+        .and_then(|idx| dir_components.get(idx + 1))
+        .filter(|ver| ver.starts_with(|c: char| c.is_ascii_digit()) && ver.contains('.'))
+        .map(|ver| ver.to_string());
+    if qt_version.is_some() || filename.starts_with("qt5") || filename.starts_with("qt6") {
+        return Some(Package {
+            ecosystem: "qt".to_string(),
+            name: Some(original_filename.trim_end_matches(".dll").trim_end_matches(".DLL").to_string()),
+            version: qt_version,
+            detail: None,
+        });
+    }
+    return None;
+}
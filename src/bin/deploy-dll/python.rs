@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+/// Whether `name` is the dll of a Python interpreter, like `python312.dll`. `python3.dll` of the stable ABI is not.
+pub fn is_python_dll(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let Some(version) = name.strip_prefix("python3").and_then(|v| v.strip_suffix(".dll")) else {
+        return false;
+    };
+    return !version.is_empty() && version.chars().all(|c| c.is_ascii_digit());
+}
+
+/// Dirs of an interpreter prefix to search for dlls: the prefix with `python3XX.dll`, and `DLLs` with the
+/// dlls of extension modules
+pub fn search_dirs(prefix: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![prefix.to_path_buf()];
+    let dlls = prefix.join("DLLs");
+    if crate::can_be_dir(&dlls) {
+        dirs.push(dlls);
+    }
+    return dirs;
+}
+
+/// Python extension modules in `dir`, sorted
+pub fn extension_modules(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut modules: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pyd")))
+        .collect();
+    modules.sort();
+    return modules;
+}
+
+/// Major and minor version of an interpreter dll stem, like (3, 12) for `python312`
+fn version_of(stem: &str) -> Option<(u32, u32)> {
+    let minor = stem.strip_prefix("python3")?.parse().ok()?;
+    return Some((3, minor));
+}
+
+/// Stem of the interpreter dll in the prefix, like `python312`. The newest version wins if there are several.
+pub fn version_stem(prefix: &Path) -> Option<String> {
+    let stems: Vec<String> = std::fs::read_dir(prefix).ok()?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_str().map(|n| n.to_string()))
+        .filter(|name| is_python_dll(name))
+        .map(|name| name[..name.len() - ".dll".len()].to_lowercase())
+        .collect();
+    return newest_stem(stems);
+}
+
+/// Compare versions by number, so that `python312` is newer than `python39`
+fn newest_stem(stems: Vec<String>) -> Option<String> {
+    return stems.into_iter().max_by_key(|stem| version_of(stem));
+}
+
+/// Extension modules of the standard library. The embeddable package has them in the prefix, and installed
+/// interpreters in `DLLs`.
+pub fn stdlib_extension_modules(prefix: &Path) -> Vec<PathBuf> {
+    let modules = extension_modules(&prefix.join("DLLs"));
+    if !modules.is_empty() {
+        return modules;
+    }
+    return extension_modules(prefix);
+}
+
+/// `._pth` file that limits `sys.path` to the zipped standard library, the application dir and `DLLs` if the
+/// application has extension modules in it
+pub fn default_path_file(stem: &str, has_dlls_dir: bool) -> String {
+    let mut content = format!("{stem}.zip\n.\n");
+    if has_dlls_dir {
+        content.push_str("DLLs\n");
+    }
+    return content;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_version_by_number() {
+        let stems = vec!["python39".to_string(), "python312".to_string(), "python310".to_string()];
+        assert_eq!(newest_stem(stems).as_deref(), Some("python312"));
+        assert_eq!(version_of("python39"), Some((3, 9)));
+        assert_eq!(version_of("python3"), None);
+    }
+}
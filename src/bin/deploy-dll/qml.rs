@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// A QML module import, like `import QtQuick.Controls 2.15`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct QmlImport {
+    pub uri: String,
+    pub major: Option<u32>,
+}
+
+/// Parse `import <uri> [version] [as <name>]` lines of a .qml file. Imports of dirs like `import "controls"` are skipped.
+pub fn parse_imports(content: &str) -> Vec<QmlImport> {
+    let mut imports = Vec::new();
+    for line in content.lines() {
+        let line = line.trim().trim_end_matches(';');
+        let Some(rest) = line.strip_prefix("import ") else {
+            continue;
+        };
+        let mut tokens = rest.split_whitespace();
+        let Some(uri) = tokens.next() else {
+            continue;
+        };
+        if uri.starts_with('"') {
+            continue;
+        }
+        let major = tokens.next()
+            .and_then(|version| version.split('.').next())
+            .and_then(|major| major.parse().ok());
+        imports.push(QmlImport { uri: uri.to_string(), major });
+    }
+    return imports;
+}
+
+/// Imports of every .qml file in `dir`, recursively
+pub fn scan_dir(dir: &Path) -> BTreeSet<QmlImport> {
+    let mut imports = BTreeSet::new();
+    for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() || entry.path().extension().is_none_or(|ext| ext != "qml") {
+            continue;
+        }
+        if let Ok(content) = std::fs::read_to_string(entry.path()) {
+            imports.extend(parse_imports(&content));
+        }
+    }
+    return imports;
+}
+
+/// Modules in the JSON output of qmlimportscanner
+pub fn load_scanner_output(file: &Path) -> Result<BTreeSet<QmlImport>, String> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| format!("Failed to read \"{}\": {e}", file.display()))?;
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse \"{}\": {e}", file.display()))?;
+    let imports = entries.iter()
+        .filter(|entry| entry["type"] == "module")
+        .filter_map(|entry| {
+            let uri = entry["name"].as_str()?.to_string();
+            let major = entry["version"].as_str()
+                .and_then(|version| version.split('.').next())
+                .and_then(|major| major.parse().ok());
+            return Some(QmlImport { uri, major });
+        })
+        .collect();
+    return Ok(imports);
+}
+
+/// The QML import dir of a Qt prefix. vcpkg puts it in `Qt6/qml`, and MSYS2 in `share/qt6/qml`
+pub fn qml_dir(prefix: &Path) -> Option<PathBuf> {
+    for dir in ["qml", "Qt6/qml", "Qt5/qml", "share/qt6/qml", "share/qt5/qml"] {
+        let dir = prefix.join(dir);
+        if crate::can_be_dir(&dir) {
+            return Some(dir);
+        }
+    }
+    return None;
+}
+
+/// Dir of a module relative to the QML import dir, like `QtQuick/Controls`.
+/// Qt 5 puts some modules in versioned dirs like `QtQuick/Controls.2`.
+fn module_dir(qml_dir: &Path, import: &QmlImport) -> Option<String> {
+    let relative = import.uri.replace('.', "/");
+    let mut candidates = vec![relative.clone()];
+    if let Some(major) = import.major {
+        candidates.push(format!("{relative}.{major}"));
+    }
+    return candidates.into_iter().find(|dir| crate::is_file(&qml_dir.join(dir).join("qmldir")));
+}
+
+/// Modules that a module depends on, from `depends` and `import` lines of its qmldir
+fn qmldir_imports(module_dir: &Path) -> Vec<QmlImport> {
+    let Ok(content) = std::fs::read_to_string(module_dir.join("qmldir")) else {
+        return Vec::new();
+    };
+    let mut imports = Vec::new();
+    for line in content.lines() {
+        let mut tokens = line.split_whitespace().peekable();
+        if tokens.peek() == Some(&"optional") {
+            tokens.next();
+        }
+        if !matches!(tokens.next(), Some("depends") | Some("import")) {
+            continue;
+        }
+        let Some(uri) = tokens.next() else {
+            continue;
+        };
+        let major = tokens.next()
+            .and_then(|version| version.split('.').next())
+            .and_then(|major| major.parse().ok());
+        imports.push(QmlImport { uri: uri.to_string(), major });
+    }
+    return imports;
+}
+
+/// Files of a module, without the modules nested in it, like `QtQuick/Controls` in `QtQuick`.
+/// Plugins of the other build type are skipped.
+pub fn module_files(module_dir: &Path, debug: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let walker = walkdir::WalkDir::new(module_dir).sort_by_file_name().into_iter()
+        .filter_entry(|e| e.path() == module_dir || !e.file_type().is_dir() || !crate::is_file(&e.path().join("qmldir")));
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dll")) && !crate::qt::matches_build_type(path, debug) {
+            continue;
+        }
+        files.push(path.to_path_buf());
+    }
+    return files;
+}
+
+/// Find `imports` and the modules they depend on in `qml_dir`. Returns dirs of the modules relative to `qml_dir`,
+/// keyed by their uri, and the imports that are not found.
+pub fn resolve_modules(qml_dir: &Path, imports: &BTreeSet<QmlImport>) -> (BTreeMap<String, String>, BTreeSet<String>) {
+    let mut modules = BTreeMap::new();
+    let mut missing = BTreeSet::new();
+    let mut queue: VecDeque<QmlImport> = imports.iter().cloned().collect();
+    while let Some(import) = queue.pop_front() {
+        if modules.contains_key(&import.uri) || missing.contains(&import.uri) {
+            continue;
+        }
+        let Some(dir) = module_dir(qml_dir, &import) else {
+            missing.insert(import.uri);
+            continue;
+        };
+        let abs_dir = qml_dir.join(&dir);
+        queue.extend(qmldir_imports(&abs_dir));
+        queue.extend(scan_dir_shallow(&abs_dir));
+        modules.insert(import.uri, dir);
+    }
+    return (modules, missing);
+}
+
+/// Imports of .qml files of a module, without its nested modules
+fn scan_dir_shallow(module_dir: &Path) -> Vec<QmlImport> {
+    return module_files(module_dir, false).iter()
+        .filter(|f| f.extension().is_some_and(|ext| ext == "qml"))
+        .filter_map(|f| std::fs::read_to_string(f).ok())
+        .flat_map(|content| parse_imports(&content))
+        .collect();
+}
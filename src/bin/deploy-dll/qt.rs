@@ -0,0 +1,124 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use crate::build_type::{self, BuildType};
+
+/// Plugin dirs required by each Qt module, like windeployqt does
+const MODULE_PLUGINS: [(&str, &[&str]); 15] = [
+    ("gui", &["platforms", "imageformats", "iconengines", "platforminputcontexts"]),
+    ("widgets", &["styles"]),
+    ("network", &["tls", "networkinformation", "bearer"]),
+    ("sql", &["sqldrivers"]),
+    ("multimedia", &["multimedia", "mediaservice", "audio"]),
+    ("printsupport", &["printsupport"]),
+    ("positioning", &["position"]),
+    ("location", &["geoservices"]),
+    ("sensors", &["sensors"]),
+    ("serialbus", &["canbus"]),
+    ("texttospeech", &["texttospeech"]),
+    ("virtualkeyboard", &["platforminputcontexts"]),
+    ("webview", &["webview"]),
+    ("3drender", &["sceneparsers", "geometryloaders", "renderers"]),
+    ("3dinput", &["3dinputdevices"]),
+];
+
+/// Only these platform plugins are deployed, the others are for testing or other systems
+const PLATFORM_PLUGINS: [&str; 1] = ["qwindows"];
+
+/// Module name of a Qt dll, like `gui` for `Qt6Gui.dll` and `Qt6Guid.dll`
+pub fn module_of(name: &str) -> Option<String> {
+    let name = name.to_lowercase();
+    let stem = name.strip_suffix(".dll")?;
+    let module = stem.strip_prefix("qt5").or_else(|| stem.strip_prefix("qt6"))?;
+    let build_type = build_type::qt_build_type(&name)?;
+    let module = if build_type == BuildType::Debug { &module[..module.len() - 1] } else { module };
+    return Some(module.to_string());
+}
+
+/// The Qt prefix that `dll` is installed in, like `C:/Qt/6.6.0/msvc2019_64` for
+/// `C:/Qt/6.6.0/msvc2019_64/bin/Qt6Core.dll`
+pub fn prefix_of(dll: &Path) -> Option<PathBuf> {
+    return Some(dll.parent()?.parent()?.to_path_buf());
+}
+
+/// The plugin dir of a Qt prefix. vcpkg puts it in `Qt6/plugins`, and MSYS2 in `share/qt6/plugins`
+pub fn plugins_dir(prefix: &Path) -> Option<PathBuf> {
+    for dir in ["plugins", "Qt6/plugins", "Qt5/plugins", "share/qt6/plugins", "share/qt5/plugins"] {
+        let dir = prefix.join(dir);
+        if crate::can_be_dir(&dir) {
+            return Some(dir);
+        }
+    }
+    return None;
+}
+
+/// Plugin dirs required by `modules`, sorted
+pub fn plugin_types(modules: &BTreeSet<String>) -> BTreeSet<&'static str> {
+    let mut types = BTreeSet::new();
+    for (module, plugin_types) in MODULE_PLUGINS {
+        if modules.contains(module) {
+            types.extend(plugin_types.iter());
+        }
+    }
+    return types;
+}
+
+/// Whether `file` is the debug build of a plugin, like `qjpegd.dll` next to `qjpeg.dll`
+fn is_debug_plugin(file: &Path) -> bool {
+    let name = file.file_name().unwrap().to_str().unwrap_or_default();
+    let Some(stem) = name.strip_suffix("d.dll") else {
+        return false;
+    };
+    return crate::is_file(&file.with_file_name(format!("{stem}.dll")));
+}
+
+/// Whether `file` has a debug build next to it, like `qjpegd.dll` next to `qjpeg.dll`
+fn has_debug_plugin(file: &Path) -> bool {
+    let stem = file.file_stem().unwrap().to_str().unwrap_or_default();
+    return crate::is_file(&file.with_file_name(format!("{stem}d.dll")));
+}
+
+/// Whether plugin `file` should be deployed for the build type. A plugin is skipped if it has a build of the other
+/// type next to it, like `qjpegd.dll` for debug builds and `qjpeg.dll` for release builds.
+pub fn matches_build_type(file: &Path, debug: bool) -> bool {
+    return if debug { !has_debug_plugin(file) } else { !is_debug_plugin(file) };
+}
+
+/// Plugin dlls of `plugin_type` in `plugins_dir`, sorted. When both debug and release builds of a plugin exist,
+/// the one that matches the build type is taken.
+pub fn plugins(plugins_dir: &Path, plugin_type: &str, debug: bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(plugins_dir.join(plugin_type)) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dll")))
+        .filter(|p| matches_build_type(p, debug))
+        .filter(|p| {
+            if plugin_type != "platforms" {
+                return true;
+            }
+            let stem = p.file_stem().unwrap().to_str().unwrap_or_default().to_lowercase();
+            return PLATFORM_PLUGINS.iter().any(|name| stem == *name || stem == format!("{name}d"));
+        })
+        .collect();
+    plugins.sort();
+    return plugins;
+}
+
+/// Content of `qt.conf` that points Qt to plugins in `plugin_dir`, relative to the application
+pub fn qt_conf(plugin_dir: &str) -> String {
+    return format!("[Paths]\nPrefix = .\nPlugins = {plugin_dir}\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_names() {
+        assert_eq!(module_of("Qt6VirtualKeyboard.dll").as_deref(), Some("virtualkeyboard"));
+        assert_eq!(module_of("Qt6VirtualKeyboardd.dll").as_deref(), Some("virtualkeyboard"));
+        assert_eq!(module_of("Qt5Gamepad.dll").as_deref(), Some("gamepad"));
+        assert_eq!(module_of("Qt6Guid.dll").as_deref(), Some("gui"));
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+use std::process::{Command, exit};
+
+/// Width of hex columns in a line of `objdump -s`, like `58100000 00000000 00000000 78100000`
+const HEX_COLUMNS_WIDTH: usize = 35;
+
+/// Decode the hex dump printed by `objdump -s`
+fn parse_hex_dump(output: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for line in output.lines() {
+        if !line.starts_with(' ') {
+            continue;
+        }
+        let Some((_address, rest)) = line.trim_start().split_once(' ') else {
+            continue;
+        };
+        let hex: String = rest.get(..HEX_COLUMNS_WIDTH).unwrap_or(rest).chars().filter(|c| !c.is_whitespace()).collect();
+        for pair in hex.as_bytes().chunks(2) {
+            let Ok(byte) = u8::from_str_radix(std::str::from_utf8(pair).unwrap_or_default(), 16) else {
+                break;
+            };
+            bytes.push(byte);
+        }
+    }
+    return bytes;
+}
+
+fn is_filename_char(c: char) -> bool {
+    return c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+');
+}
+
+/// Names ending with `.dll` in a decoded string, without their dirs
+fn dll_names_in(text: &str, names: &mut BTreeSet<String>) {
+    let lower = text.to_lowercase();
+    let mut search_from = 0;
+    while let Some(pos) = lower[search_from..].find(".dll") {
+        let end = search_from + pos + ".dll".len();
+        search_from = end;
+        // Names like foo.dll.manifest are not dlls
+        if lower[end..].starts_with(|c: char| is_filename_char(c)) {
+            continue;
+        }
+        let start = lower[..end - ".dll".len()].rfind(|c: char| !is_filename_char(c)).map(|i| i + 1).unwrap_or(0);
+        let name = &lower[start..end];
+        if name.len() > ".dll".len() {
+            names.insert(name.to_string());
+        }
+    }
+}
+
+/// Find dll names in ASCII and UTF-16 strings of `bytes`
+fn find_dll_names(bytes: &[u8]) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+
+    let ascii: String = bytes.iter().map(|b| if b.is_ascii_graphic() { *b as char } else { '\0' }).collect();
+    dll_names_in(&ascii, &mut names);
+
+    // LoadLibraryW takes UTF-16 strings, whose ASCII chars are followed by zero bytes
+    for offset in 0..2 {
+        let wide: String = bytes[offset.min(bytes.len())..].chunks(2)
+            .map(|pair| match pair {
+                [c, 0] if c.is_ascii_graphic() => *c as char,
+                _ => '\0',
+            })
+            .collect();
+        dll_names_in(&wide, &mut names);
+    }
+    return names;
+}
+
+/// Dll names that appear as strings in the `.rdata` section of `file`, which may be loaded by `LoadLibrary`
+pub fn dll_strings(file: &str, objdump_file: &str) -> BTreeSet<String> {
+    let output = Command::new(objdump_file).args(["-s", "-j", ".rdata", file]).output()
+        .unwrap_or_else(|_| panic!("Failed to run objdump at {}", objdump_file));
+    if !output.status.success() {
+        eprintln!("{} -s -j .rdata {} failed with error code {}", objdump_file, file, output.status);
+        eprintln!("The std error is: {}", String::from_utf8_lossy(&output.stderr));
+        exit(1);
+    }
+    let output = String::from_utf8_lossy(&output.stdout);
+    return find_dll_names(&parse_hex_dump(&output));
+}
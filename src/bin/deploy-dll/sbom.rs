@@ -0,0 +1,122 @@
+use std::path::Path;
+use std::process::exit;
+use serde_json::{json, Value};
+use crate::Context;
+use crate::file_version::file_version;
+use crate::package::Package;
+
+/// A file shipped with the deployment
+struct Component {
+    /// Path relative to the deploy root, which is unique even if files share a name
+    bom_ref: String,
+    name: String,
+    is_application: bool,
+    source: String,
+    sha256: String,
+    version: Option<String>,
+    package: Option<Package>,
+}
+
+fn collect_components(context: &Context) -> Vec<Component> {
+    let mut components = Vec::new();
+    for file in &context.deployed_dlls {
+        let name = file.file_name().unwrap().to_str().unwrap().to_string();
+        let file_str = file.to_str().unwrap();
+        let is_application = context.root_binaries.iter().any(|b| b == file_str);
+        // Qt plugins are resolved by their path in deploy dir, like platforms/qwindows.dll
+        let relative = crate::manifest::relative_path(&context.root_dir, file).to_lowercase();
+        let bom_ref = relative.clone();
+        if components.iter().any(|c: &Component| c.bom_ref == bom_ref) {
+            continue;
+        }
+        let resolution = match context.resolved.get(&relative).or_else(|| context.resolved.get(&name.to_lowercase())) {
+            Some(r) if !is_application => r.clone(),
+            _ => crate::lock::resolve(file_str, None),
+        };
+        components.push(Component {
+            bom_ref,
+            name,
+            is_application,
+            version: file_version(file),
+            package: context.package_of(&resolution.source),
+            source: resolution.source,
+            sha256: resolution.sha256,
+        });
+    }
+    return components;
+}
+
+fn cyclonedx_component(c: &Component) -> Value {
+    let source_dir = Path::new(&c.source).parent()
+        .map(|p| p.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let mut properties = vec![
+        json!({"name": "deploy-dll:source", "value": c.source}),
+        json!({"name": "deploy-dll:source-dir", "value": source_dir}),
+    ];
+    if let Some(package) = c.package.clone() {
+        properties.push(json!({"name": "deploy-dll:package-manager", "value": package.ecosystem}));
+        if let Some(name) = package.name {
+            properties.push(json!({"name": "deploy-dll:package", "value": name}));
+        }
+        if let Some(version) = package.version {
+            properties.push(json!({"name": "deploy-dll:package-version", "value": version}));
+        }
+        if let Some(detail) = package.detail {
+            properties.push(json!({"name": "deploy-dll:package-detail", "value": detail}));
+        }
+    }
+
+    let mut component = json!({
+        "type": if c.is_application { "application" } else { "library" },
+        "bom-ref": c.bom_ref,
+        "name": c.name,
+        "hashes": [{"alg": "SHA-256", "content": c.sha256}],
+        "properties": properties,
+    });
+    if let Some(version) = &c.version {
+        component["version"] = json!(version);
+    }
+    return component;
+}
+
+/// Write a CycloneDX 1.5 SBOM of every file in the deployment.
+pub fn write_cyclonedx(context: &Context, output: &str) {
+    let components = collect_components(context);
+    let (applications, libraries): (Vec<&Component>, Vec<&Component>) =
+        components.iter().partition(|c| c.is_application);
+
+    let mut bom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "deploy-dll",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+        },
+        "components": libraries.iter().map(|c| cyclonedx_component(c)).collect::<Vec<Value>>(),
+    });
+    if let Some((first, others)) = applications.split_first() {
+        bom["metadata"]["component"] = cyclonedx_component(first);
+        let root_ref = first.bom_ref.clone();
+        let mut depends_on: Vec<String> = libraries.iter().map(|c| c.bom_ref.clone()).collect();
+        for other in others {
+            bom["components"].as_array_mut().unwrap().push(cyclonedx_component(other));
+            depends_on.push(other.bom_ref.clone());
+        }
+        bom["dependencies"] = json!([{"ref": root_ref, "dependsOn": depends_on}]);
+    }
+
+    let mut content = serde_json::to_string_pretty(&bom).expect("Serialize SBOM");
+    content.push('\n');
+    if let Err(e) = std::fs::write(output, content) {
+        eprintln!("Failed to write SBOM \"{output}\" because {e}");
+        exit(11);
+    }
+    println!("Wrote SBOM with {} components to \"{output}\"", components.len());
+}
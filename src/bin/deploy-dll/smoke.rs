@@ -0,0 +1,179 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Status of a process that failed to start because a dll is not found
+pub const STATUS_DLL_NOT_FOUND: u32 = 0xC0000135;
+/// Status of a process that failed to start because a dll is of another architecture
+pub const STATUS_INVALID_IMAGE_FORMAT: u32 = 0xC000007B;
+
+/// Dlls that a fresh Wine prefix would ask to install, and the menu builder that writes into the home dir
+const WINE_DLL_OVERRIDES: &str = "mscoree,mshtml=;winemenubuilder.exe=d";
+
+/// A dll that the loader failed to load
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoaderError {
+    pub dll: String,
+    /// The binary that imports it, as a Windows path
+    pub needed_by: String,
+    /// Why it is not loaded, like "not found"
+    pub reason: String,
+}
+
+/// The text between `start` and `end` in `line`
+fn between<'a>(line: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let from = line.find(start)? + start.len();
+    let to = from + line[from..].find(end)?;
+    return Some(&line[from..to]);
+}
+
+/// Loader errors in the stderr of Wine, like
+/// `err:module:import_dll Library foo.dll (which is needed by L"Z:\\app\\app.exe") not found` and
+/// `err:module:import_dll Loading library foo.dll (which is needed by L"Z:\\app\\app.exe") failed (error c000007b).`
+pub fn parse_loader_errors(stderr: &str) -> Vec<LoaderError> {
+    let mut errors = Vec::new();
+    for line in stderr.lines() {
+        let Some((_, message)) = line.split_once("err:module:import_dll ") else {
+            continue;
+        };
+        let dll = message.strip_prefix("Library ").or_else(|| message.strip_prefix("Loading library "))
+            .and_then(|rest| rest.split(" (").next());
+        let Some(dll) = dll else {
+            continue;
+        };
+        let needed_by = between(message, "needed by L\"", "\")").unwrap_or_default().replace("\\\\", "\\");
+        let reason = match message.rsplit_once(") ") {
+            Some((_, reason)) => reason.trim_end_matches('.').to_string(),
+            None => "failed".to_string(),
+        };
+        let error = LoaderError { dll: dll.to_lowercase(), needed_by, reason };
+        if !errors.contains(&error) {
+            errors.push(error);
+        }
+    }
+    return errors;
+}
+
+/// The value of `WINEDLLOVERRIDES`, with `extra` overrides like `msvcp140=n` appended
+pub fn wine_dll_overrides(extra: &[String]) -> String {
+    let mut overrides = vec![WINE_DLL_OVERRIDES.to_string()];
+    overrides.extend(extra.iter().cloned());
+    return overrides.join(";");
+}
+
+/// How a smoke run ended
+pub struct SmokeRun {
+    /// Exit code, or None if it was killed after the timeout
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+/// Run `command` until it exits or `timeout` passes, capturing its stderr
+pub fn run(mut command: Command, timeout: Duration) -> Result<SmokeRun, String> {
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::piped()).spawn()
+        .map_err(|e| format!("Failed to start {:?}: {e}", command.get_program()))?;
+    let mut stderr = child.stderr.take().unwrap();
+    // Children of the process may keep stderr open after it's killed, so what's read so far is taken
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let reader = {
+        let captured = captured.clone();
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            while let Ok(len @ 1..) = stderr.read(&mut buffer) {
+                captured.lock().unwrap().extend_from_slice(&buffer[..len]);
+            }
+        })
+    };
+
+    let deadline = Instant::now() + timeout;
+    let exit_code = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status.code(),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(100)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+            Err(e) => return Err(format!("Failed to wait for {:?}: {e}", command.get_program())),
+        }
+    };
+    let grace = Instant::now() + Duration::from_secs(2);
+    while !reader.is_finished() && Instant::now() < grace {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let stderr = String::from_utf8_lossy(&captured.lock().unwrap()).to_string();
+    return Ok(SmokeRun { exit_code, stderr });
+}
+
+/// A throwaway Wine prefix, removed when dropped
+pub struct WinePrefix {
+    pub dir: PathBuf,
+    /// wineserver of the Wine that runs in the prefix, to stop it before removing the prefix
+    wineserver: Option<PathBuf>,
+}
+
+impl WinePrefix {
+    pub fn create(wineserver: Option<PathBuf>) -> Result<WinePrefix, String> {
+        let dir = std::env::temp_dir().join(format!("deploy-dll-smoke-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create \"{}\": {e}", dir.display()))?;
+        return Ok(WinePrefix { dir, wineserver });
+    }
+}
+
+impl Drop for WinePrefix {
+    fn drop(&mut self) {
+        // Processes of the app may still be running in the prefix
+        if let Some(wineserver) = &self.wineserver {
+            let _ = Command::new(wineserver).arg("-k").env("WINEPREFIX", &self.dir).status();
+        }
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// `PATH` for the app, with only the dir of `runner` and the system dirs of the host
+pub fn clean_path(runner: &Path) -> std::ffi::OsString {
+    let mut dirs: Vec<PathBuf> = runner.parent().into_iter().map(|d| d.to_path_buf()).collect();
+    if cfg!(target_os = "windows") {
+        dirs.extend(["C:/Windows/System32", "C:/Windows"].map(PathBuf::from));
+    } else {
+        dirs.extend(["/usr/bin", "/bin"].map(PathBuf::from));
+    }
+    return std::env::join_paths(dirs).unwrap_or_default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loader_errors_of_wine() {
+        let stderr = "\
+0024:err:module:import_dll Library libfoo.dll (which is needed by L\"Z:\\\\app\\\\app.exe\") not found
+0024:err:module:import_dll Loading library LibBar.dll (which is needed by L\"Z:\\\\app\\\\libfoo.dll\") failed (error c000007b).
+0024:err:module:import_dll Library libfoo.dll (which is needed by L\"Z:\\\\app\\\\app.exe\") not found
+0024:fixme:ntdll:NtQuerySystemInformation info_class SYSTEM_PERFORMANCE_INFORMATION
+";
+        assert_eq!(parse_loader_errors(stderr), [
+            LoaderError { dll: "libfoo.dll".to_string(), needed_by: "Z:\\app\\app.exe".to_string(), reason: "not found".to_string() },
+            LoaderError { dll: "libbar.dll".to_string(), needed_by: "Z:\\app\\libfoo.dll".to_string(),
+                          reason: "failed (error c000007b)".to_string() },
+        ]);
+        assert!(parse_loader_errors("0024:fixme:heap:RtlSetHeapInformation\n").is_empty());
+    }
+
+    #[test]
+    fn text_between_markers() {
+        assert_eq!(between("needed by L\"app.exe\") not found", "L\"", "\")"), Some("app.exe"));
+        assert_eq!(between("no markers", "L\"", "\")"), None);
+    }
+
+    #[test]
+    fn extra_dll_overrides_come_last() {
+        assert_eq!(wine_dll_overrides(&[]), WINE_DLL_OVERRIDES);
+        assert_eq!(wine_dll_overrides(&["msvcp140=n".to_string()]), format!("{WINE_DLL_OVERRIDES};msvcp140=n"));
+    }
+}
@@ -0,0 +1,46 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use crate::manifest;
+
+/// Files that `root` loads directly or indirectly
+fn dlls_of(root: &Path, loads: &BTreeMap<PathBuf, BTreeSet<PathBuf>>) -> BTreeSet<PathBuf> {
+    let mut dlls = BTreeSet::new();
+    let mut queue: VecDeque<&Path> = VecDeque::from([root]);
+    while let Some(binary) = queue.pop_front() {
+        for dep in loads.get(binary).into_iter().flatten() {
+            if dlls.insert(dep.clone()) {
+                queue.push_back(dep);
+            }
+        }
+    }
+    return dlls;
+}
+
+/// Print the dlls shared by all targets, shared by some of them, and used by only one.
+/// Binaries are keyed by path, so that files of the same name in different dirs are told apart,
+/// and shown relative to `root_dir`.
+pub fn print_report(root_dir: &Path, roots: &[String], loads: &BTreeMap<PathBuf, BTreeSet<PathBuf>>) {
+    let mut users: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for root in roots {
+        let root_path = manifest::absolute_path(Path::new(root));
+        let name = manifest::relative_path(root_dir, &root_path);
+        for dll in dlls_of(&root_path, loads) {
+            users.entry(manifest::relative_path(root_dir, &dll)).or_default().push(name.clone());
+        }
+    }
+
+    println!("Dlls shared by all {} targets:", roots.len());
+    for (dll, _) in users.iter().filter(|(_, u)| u.len() == roots.len()) {
+        println!("  {dll}");
+    }
+    if roots.len() > 2 {
+        println!("Dlls shared by some targets:");
+        for (dll, users) in users.iter().filter(|(_, u)| u.len() > 1 && u.len() < roots.len()) {
+            println!("  {dll} <- {}", users.join(", "));
+        }
+    }
+    println!("Dlls used by only one target:");
+    for (dll, users) in users.iter().filter(|(_, u)| u.len() == 1) {
+        println!("  {dll} <- {}", users[0]);
+    }
+}
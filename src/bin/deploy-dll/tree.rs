@@ -0,0 +1,57 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use crate::manifest::{self, Manifest};
+
+/// Extensions of binaries that `--tree` deploys for
+const BINARY_EXTENSIONS: [&str; 3] = ["exe", "dll", "pyd"];
+
+/// Every exe, dll and pyd in `dir`, recursively, except those deployed by previous runs into `deploy_dirs`.
+/// Exes come first, so that dlls loaded by them can use the dlls deployed next to them. Otherwise sorted.
+pub fn discover(dir: &Path, deploy_dirs: &[&Path]) -> Vec<PathBuf> {
+    let deployed = deployed_files(deploy_dirs);
+    let mut binaries = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let is_binary = entry.path().extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| BINARY_EXTENSIONS.iter().any(|b| ext.eq_ignore_ascii_case(b)));
+        if is_binary && !deployed.contains(&manifest::absolute_path(entry.path())) {
+            binaries.push(entry.path().to_path_buf());
+        }
+    }
+    binaries.sort_by_key(|b| !is_exe(b));
+    return binaries;
+}
+
+fn is_exe(file: &Path) -> bool {
+    return file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+}
+
+/// Absolute paths of files recorded in the deployment manifests of `dirs`
+fn deployed_files(dirs: &[&Path]) -> BTreeSet<PathBuf> {
+    let mut files = BTreeSet::new();
+    for dir in dirs {
+        if let Some(manifest) = Manifest::load(dir) {
+            files.extend(manifest.files().map(|entry| manifest::absolute_path(&dir.join(&entry.path))));
+        }
+    }
+    return files;
+}
+
+/// Dirs of the exes in `binaries`. A dll loaded by an exe finds its imports in the dir of the exe as well.
+pub fn app_dirs(binaries: &[String]) -> Vec<PathBuf> {
+    let dirs: BTreeSet<&Path> = binaries.iter()
+        .map(Path::new)
+        .filter(|b| is_exe(b))
+        .filter_map(|b| b.parent())
+        .collect();
+    return dirs.into_iter().map(|d| d.to_path_buf()).collect();
+}
+
+/// Dirs that contain `binaries`, which are searched first for the dlls that binaries in other dirs require
+pub fn binary_dirs(binaries: &[String]) -> Vec<String> {
+    let dirs: BTreeSet<&Path> = binaries.iter().filter_map(|b| Path::new(b).parent()).collect();
+    return dirs.iter().map(|d| d.to_str().unwrap().to_string()).collect();
+}
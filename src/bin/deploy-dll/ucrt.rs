@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+
+/// Whether `name` is a dll of the Universal CRT that can be deployed app-locally
+pub fn is_ucrt_dll(name: &str) -> bool {
+    let name = name.to_lowercase();
+    return name == "ucrtbase.dll" || name.starts_with("api-ms-win-crt-");
+}
+
+/// Whether `name` is an API set like `api-ms-win-core-synch-l1-2-0.dll`, which is resolved by the loader
+pub fn is_api_set(name: &str) -> bool {
+    let name = name.to_lowercase();
+    return name.starts_with("api-ms-win-") || name.starts_with("ext-ms-win-");
+}
+
+/// Parse a version dir name like `10.0.22621.0`
+fn parse_version(name: &str) -> Option<Vec<u32>> {
+    return name.split('.').map(|part| part.parse().ok()).collect();
+}
+
+/// The Windows 10+ SDK root, like `C:/Program Files (x86)/Windows Kits/10`
+fn default_sdk_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("WindowsSdkDir") {
+        return Some(PathBuf::from(dir));
+    }
+    let program_files = std::env::var("ProgramFiles(x86)").ok()?;
+    let dir = Path::new(&program_files).join("Windows Kits/10");
+    return crate::can_be_dir(&dir).then_some(dir);
+}
+
+/// `Redist/<version>/ucrt/DLLs/<arch>` of the SDK, of `WindowsSDKVersion` or the newest version.
+/// Older SDKs have `Redist/ucrt/DLLs/<arch>` instead.
+pub fn dll_dir(sdk_dir: Option<&Path>, arch: &str) -> Result<PathBuf, String> {
+    let Some(sdk_dir) = sdk_dir.map(|d| d.to_path_buf()).or_else(default_sdk_dir) else {
+        return Err("Windows SDK is not found, set WindowsSdkDir or --windows-sdk-dir".to_string());
+    };
+    let redist = sdk_dir.join("Redist");
+    let ucrt_dir = |dir: &Path| dir.join("ucrt/DLLs").join(arch);
+
+    if let Ok(version) = std::env::var("WindowsSDKVersion") {
+        let dir = ucrt_dir(&redist.join(version.trim_end_matches(['\\', '/'])));
+        if crate::can_be_dir(&dir) {
+            return Ok(dir);
+        }
+    }
+    let newest = std::fs::read_dir(&redist).into_iter().flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| Some((parse_version(e.file_name().to_str()?)?, e.path())))
+        .filter(|(_, dir)| crate::can_be_dir(&ucrt_dir(dir)))
+        .max();
+    if let Some((_, dir)) = newest {
+        return Ok(ucrt_dir(&dir));
+    }
+    let dir = ucrt_dir(&redist);
+    if crate::can_be_dir(&dir) {
+        return Ok(dir);
+    }
+    return Err(format!("No Universal CRT redistributable for {arch} in \"{}\"", redist.display()));
+}
+
+/// Every dll of the redistributable in `dll_dir`: ucrtbase.dll and the api-ms-win-* forwarders to it,
+/// which have to be deployed together on systems without the Universal CRT
+pub fn redist_dlls(dll_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dll_dir) else {
+        return Vec::new();
+    };
+    let mut dlls: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dll")))
+        .collect();
+    dlls.sort();
+    return dlls;
+}
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Architecture name used by the redist folder for binaries of `binary_format`
+pub fn arch_of(binary_format: &str) -> Option<&'static str> {
+    return match binary_format {
+        "pei-x86-64" => Some("x64"),
+        "pei-i386" => Some("x86"),
+        "pei-aarch64-little" => Some("arm64"),
+        _ => None,
+    };
+}
+
+/// Parse a version dir name like `14.38.33130`
+fn parse_version(name: &str) -> Option<Vec<u32>> {
+    return name.split('.').map(|part| part.parse().ok()).collect();
+}
+
+/// The newest `VC/Redist/MSVC/<version>` dir of a Visual Studio installation
+fn newest_redist_dir(installation: &Path) -> Option<PathBuf> {
+    let msvc = installation.join("VC/Redist/MSVC");
+    return std::fs::read_dir(msvc).ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| Some((parse_version(e.file_name().to_str()?)?, e.path())))
+        .max()
+        .map(|(_, dir)| dir);
+}
+
+/// Installation paths of Visual Studio reported by vswhere, newest first
+fn vswhere_installations() -> Vec<PathBuf> {
+    let Ok(program_files) = std::env::var("ProgramFiles(x86)") else {
+        return Vec::new();
+    };
+    let vswhere = Path::new(&program_files).join("Microsoft Visual Studio/Installer/vswhere.exe");
+    if !crate::is_file(&vswhere) {
+        return Vec::new();
+    }
+    let Ok(output) = Command::new(vswhere)
+        .args(["-latest", "-products", "*", "-property", "installationPath"])
+        .output() else {
+        return Vec::new();
+    };
+    return String::from_utf8_lossy(&output.stdout).lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect();
+}
+
+/// Find the `VC/Redist/MSVC/<version>` dir, from `VCToolsRedistDir` set by the developer command prompt,
+/// or the newest one of the Visual Studio installation found by vswhere.
+pub fn locate_redist_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("VCToolsRedistDir") {
+        let dir = PathBuf::from(dir);
+        if crate::can_be_dir(&dir) {
+            return Some(dir);
+        }
+    }
+    return vswhere_installations().iter().find_map(|installation| newest_redist_dir(installation));
+}
+
+/// Dirs like `<arch>/Microsoft.VC143.CRT` and `<arch>/Microsoft.VC143.OpenMP` in the redist dir
+pub fn runtime_dirs(redist_dir: &Path, arch: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(redist_dir.join(arch)) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("Microsoft.VC")))
+        .collect();
+    dirs.sort();
+    return dirs;
+}
+
+/// The `Microsoft.VC*.CRT` dir in `runtime_dirs`
+pub fn crt_dir(runtime_dirs: &[PathBuf]) -> Option<&PathBuf> {
+    return runtime_dirs.iter()
+        .find(|d| d.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.ends_with(".CRT")));
+}
+
+/// Dlls of the CRT that are deployed together, so that they come from the same version. Concurrency Runtime
+/// (concrt) is only deployed when it is imported.
+pub fn crt_set(crt_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(crt_dir) else {
+        return Vec::new();
+    };
+    let mut dlls: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            let name = p.file_name().unwrap().to_str().unwrap_or_default().to_lowercase();
+            return name.ends_with(".dll") && !name.starts_with("concrt");
+        })
+        .collect();
+    dlls.sort();
+    return dlls;
+}
+
+/// The redistributable installer, like `vc_redist.x64.exe`
+pub fn installer(redist_dir: &Path, arch: &str) -> PathBuf {
+    return redist_dir.join(format!("vc_redist.{arch}.exe"));
+}
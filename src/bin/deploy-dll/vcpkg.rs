@@ -0,0 +1,143 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::lock::LockedDll;
+
+/// The vcpkg port that installed a file
+#[derive(Debug, Clone)]
+pub struct VcpkgOwner {
+    pub port: String,
+    pub version: String,
+    pub triplet: String,
+    /// The `installed` dir of vcpkg
+    pub installed_dir: PathBuf,
+    /// Files installed by the port, relative to `installed_dir`
+    pub files: Vec<String>,
+}
+
+/// Find the `installed` (or `vcpkg_installed`) dir that contains `file`
+fn installed_dir_of(file: &Path) -> Option<PathBuf> {
+    for dir in file.ancestors().skip(1) {
+        let name = dir.file_name()?.to_str()?;
+        if name == "installed" || name == "vcpkg_installed" {
+            let mut info = dir.to_path_buf();
+            info.push("vcpkg/info");
+            if crate::can_be_dir(&info) {
+                return Some(dir.to_path_buf());
+            }
+        }
+    }
+    return None;
+}
+
+/// Parse `<port>_<version>_<triplet>.list`
+fn parse_list_filename(filename: &str) -> Option<(String, String, String)> {
+    let stem = filename.strip_suffix(".list")?;
+    let (port, rest) = stem.split_once('_')?;
+    let (version, triplet) = rest.rsplit_once('_')?;
+    return Some((port.to_string(), version.to_string(), triplet.to_string()));
+}
+
+/// Ports in `vcpkg/info` by the files they installed. Each installed dir is indexed once, when it's first used.
+#[derive(Default)]
+pub struct OwnerIndex {
+    installed_dirs: RefCell<BTreeMap<PathBuf, InstalledIndex>>,
+}
+
+#[derive(Default)]
+struct InstalledIndex {
+    owners: Vec<VcpkgOwner>,
+    /// Lower-case path relative to the installed dir -> index in `owners`
+    by_file: BTreeMap<String, usize>,
+}
+
+impl OwnerIndex {
+    /// Find the port that installed `file`.
+    pub fn find_owner(&self, file: &Path) -> Option<VcpkgOwner> {
+        let installed_dir = installed_dir_of(file)?;
+        let relative = file.strip_prefix(&installed_dir).ok()?.to_str()?.replace('\\', "/").to_lowercase();
+        let mut installed_dirs = self.installed_dirs.borrow_mut();
+        let index = installed_dirs.entry(installed_dir.clone()).or_insert_with(|| index_installed_dir(&installed_dir));
+        let owner = index.by_file.get(&relative)?;
+        return Some(index.owners[*owner].clone());
+    }
+}
+
+/// Read every `vcpkg/info/*.list` of `installed_dir`
+fn index_installed_dir(installed_dir: &Path) -> InstalledIndex {
+    let mut index = InstalledIndex::default();
+    let Ok(read_dir) = std::fs::read_dir(installed_dir.join("vcpkg/info")) else {
+        return index;
+    };
+    let mut lists: Vec<PathBuf> = read_dir
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "list"))
+        .collect();
+    lists.sort();
+
+    for list in lists {
+        let Ok(content) = std::fs::read_to_string(&list) else {
+            continue;
+        };
+        let Some((port, version, triplet)) = list.file_name().and_then(|n| n.to_str()).and_then(parse_list_filename) else {
+            continue;
+        };
+        let files: Vec<String> = content.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect();
+        for file in &files {
+            // The first port wins, like the first match of a search in sorted order
+            index.by_file.entry(file.to_lowercase()).or_insert(index.owners.len());
+        }
+        index.owners.push(VcpkgOwner {
+            port,
+            version,
+            triplet,
+            installed_dir: installed_dir.to_path_buf(),
+            files,
+        });
+    }
+    return index;
+}
+
+/// The `installed` dir of a vcpkg root, or `root` itself if it is already an installed dir (like `vcpkg_installed`)
+pub fn installed_dir(root: &Path) -> Option<PathBuf> {
+    let mut installed = root.to_path_buf();
+    installed.push("installed");
+    if crate::can_be_dir(&installed) {
+        return Some(installed);
+    }
+    if crate::can_be_dir(&root.join("vcpkg/info")) {
+        return Some(root.to_path_buf());
+    }
+    return None;
+}
+
+/// The default triplet for binaries of `binary_format`
+pub fn default_triplet(binary_format: &str) -> Option<&'static str> {
+    return match binary_format {
+        "pei-x86-64" => Some("x64-windows"),
+        "pei-i386" => Some("x86-windows"),
+        "pei-aarch64-little" => Some("arm64-windows"),
+        _ => None,
+    };
+}
+
+/// `installed/<triplet>/bin` for release builds, and `installed/<triplet>/debug/bin` for debug builds
+pub fn bin_dir(installed_dir: &Path, triplet: &str, debug: bool) -> PathBuf {
+    let mut dir = installed_dir.join(triplet);
+    if debug {
+        dir.push("debug");
+    }
+    dir.push("bin");
+    return dir;
+}
+
+/// Print the vcpkg port that provides each resolved dll
+pub fn print_report(resolved: &BTreeMap<String, LockedDll>, owners: &OwnerIndex) {
+    println!("Dlls provided by vcpkg ports:");
+    for (name, dll) in resolved {
+        if let Some(owner) = owners.find_owner(Path::new(&dll.source)) {
+            println!("  {name} <- {} {} ({})", owner.port, owner.version, owner.triplet);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::{Command, exit};
+use crate::baseline::Baseline;
+
+/// Imported symbols of each dll in the output of `objdump -p`, by lower-case dll name. Imports by ordinal are skipped.
+pub fn parse_imports(output: &str) -> BTreeMap<String, BTreeSet<String>> {
+    let mut imports: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+    for line in output.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(name) = line.trim().strip_prefix("DLL Name: ") {
+            let name = name.trim().to_lowercase();
+            imports.entry(name.clone()).or_default();
+            current = Some(name);
+            continue;
+        }
+        let Some(dll) = &current else {
+            continue;
+        };
+        if line.trim().is_empty() || !line.starts_with('\t') {
+            current = None;
+            continue;
+        }
+        // Lines of symbols look like `\t103c\t    0  CreateFileW`
+        let mut tokens = line.split_whitespace();
+        let (Some(vma), Some(_hint), Some(symbol)) = (tokens.next(), tokens.next(), tokens.next()) else {
+            continue;
+        };
+        if vma == "vma:" || symbol == "<none>" {
+            continue;
+        }
+        imports.get_mut(dll).unwrap().insert(symbol.to_string());
+    }
+    return imports;
+}
+
+/// Exported symbols in the output of `objdump -p`
+pub fn parse_exports(output: &str) -> BTreeSet<String> {
+    let mut exports = BTreeSet::new();
+    let mut in_table = false;
+    for line in output.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with("[Ordinal/Name Pointer] Table") {
+            in_table = true;
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        // Lines of the table look like `\t[   0] CreateFileW`
+        if let Some((_index, name)) = line.split_once(']') {
+            if let Some(name) = name.split_whitespace().next() {
+                exports.insert(name.to_string());
+            }
+        }
+    }
+    return exports;
+}
+
+/// Exported symbols of `file`
+pub fn exports_of(file: &Path, objdump_file: &str) -> Result<BTreeSet<String>, String> {
+    return private_headers(file, objdump_file).map(|headers| parse_exports(&headers));
+}
+
+fn private_headers(file: &Path, objdump_file: &str) -> Result<String, String> {
+    let output = Command::new(objdump_file).arg("-p").arg(file).output()
+        .unwrap_or_else(|_| panic!("Failed to run objdump at {}", objdump_file));
+    if !output.status.success() {
+        return Err(format!("{} -p {} failed with error code {}, the std error is: {}", objdump_file, file.display(),
+                           output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+}
+
+/// Exit if objdump fails
+fn exit_on_error<T>(result: Result<T, String>) -> T {
+    return result.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1);
+    });
+}
+
+/// Files in `dir` by lower-case name, since the loader finds them case-insensitively
+fn files_by_name(dir: &Path) -> BTreeMap<String, PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    return entries.filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| Some((e.file_name().to_str()?.to_lowercase(), e.path())))
+        .collect();
+}
+
+/// Problems of `binary_name` importing `symbols` from `dll` that is not next to the app. It's fine if the dll comes
+/// with the system, as long as it exports the symbols when `system_exports` records them.
+fn absent_dll_problems(binary_name: &str, dll: &str, symbols: &BTreeSet<String>, is_system: bool,
+                       system_exports: Option<&BTreeSet<String>>) -> Vec<String> {
+    if !is_system {
+        return vec![format!("{dll} required by \"{binary_name}\" is not found")];
+    }
+    let Some(system_exports) = system_exports else {
+        return Vec::new();
+    };
+    return symbols.iter().filter(|s| !system_exports.contains(*s))
+        .map(|symbol| format!("{symbol} required by \"{binary_name}\" is not exported by {dll} of the system"))
+        .collect();
+}
+
+/// Problems of `binary_name` importing `symbols` that `dll` doesn't export
+fn missing_symbols(binary_name: &str, dll: &str, symbols: &BTreeSet<String>, dll_exports: &BTreeSet<String>) -> Vec<String> {
+    return symbols.iter().filter(|s| !dll_exports.contains(*s))
+        .map(|symbol| format!("{symbol} required by \"{binary_name}\" is not exported by {dll}"))
+        .collect();
+}
+
+/// Walk the imports of `binary_file` with only the dlls next to it, and dlls that `is_baseline` tells to come with
+/// the system. Symbols of system dlls are checked if `baseline` records their exports. Returns the problems found:
+/// unresolved imports, architecture mismatches and missing symbols.
+pub fn verify(binary_file: &Path, objdump_file: &str, is_baseline: &dyn Fn(&str) -> bool, baseline: Option<&Baseline>,
+              verbose: bool) -> Vec<String> {
+    let app_dir = binary_file.parent().unwrap();
+    let mut app_files = files_by_name(app_dir);
+    // Dlls in the subdir of --dll-subdir are found through the private assembly
+    app_files.extend(crate::dll_subdir::private_assembly_dlls(binary_file));
+    let format = crate::get_file_format(binary_file.to_str().unwrap(), objdump_file);
+
+    let mut problems = Vec::new();
+    let mut exports: BTreeMap<String, Option<BTreeSet<String>>> = BTreeMap::new();
+    let mut visited = BTreeSet::new();
+    let mut queue = VecDeque::from([binary_file.to_path_buf()]);
+    while let Some(binary) = queue.pop_front() {
+        let binary_name = binary.file_name().unwrap().to_str().unwrap().to_string();
+        let headers = exit_on_error(private_headers(&binary, objdump_file));
+        for (dll, symbols) in parse_imports(&headers) {
+            let Some(location) = app_files.get(&dll) else {
+                let is_system = is_baseline(&dll);
+                if verbose && is_system {
+                    println!("{dll} of \"{binary_name}\" comes with the system");
+                }
+                let system_exports = baseline.and_then(|b| b.exports(&dll));
+                problems.extend(absent_dll_problems(&binary_name, &dll, &symbols, is_system, system_exports));
+                continue;
+            };
+
+            // Exports of a dll of another architecture are not checked
+            let dll_exports = exports.entry(dll.clone()).or_insert_with(|| {
+                if let Err(reason) = crate::check_arch(location, objdump_file, &format) {
+                    problems.push(format!("{dll} required by \"{binary_name}\" can't be loaded: {reason}"));
+                    return None;
+                }
+                return Some(exit_on_error(exports_of(location, objdump_file)));
+            });
+            let Some(dll_exports) = dll_exports else {
+                continue;
+            };
+            problems.extend(missing_symbols(&binary_name, &dll, &symbols, dll_exports));
+            if visited.insert(dll.clone()) {
+                if verbose {
+                    println!("{dll} of \"{binary_name}\" is found at \"{}\"", location.display());
+                }
+                queue.push_back(location.clone());
+            }
+        }
+    }
+    return problems;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADERS: &str = "\
+There is an import table in .idata at 0x140008000
+
+The Import Tables (interpreted .idata section contents)
+ vma:            Hint    Time      Forward  DLL       First
+                 Table   Stamp     Chain    Name      Thunk
+ 00008000\t0000803c 00000000 00000000 000086e8 000081cc
+
+\tDLL Name: KERNEL32.dll
+\tvma:  Hint/Ord Member-Name Bound-To
+\t8388\t  283  CreateFileW
+\t8396\t  625  ReadFile
+
+\tDLL Name: libfoo.dll
+\tvma:  Hint/Ord Member-Name Bound-To
+\t83a2\t    0  foo_init
+\t83ae\t   12  <none>
+
+ 00008014\t00000000 00000000 00000000 00000000 00000000
+
+There is an export table in .edata at 0x140009000
+
+[Ordinal/Name Pointer] Table
+\t[   0] app_main
+\t[   1] app_version
+
+The Function Table (interpreted .pdata section contents)
+";
+
+    fn set(items: &[&str]) -> BTreeSet<String> {
+        return items.iter().map(|s| s.to_string()).collect();
+    }
+
+    #[test]
+    fn imports_by_lower_case_dll() {
+        let imports = parse_imports(HEADERS);
+        assert_eq!(imports.keys().collect::<Vec<_>>(), ["kernel32.dll", "libfoo.dll"]);
+        assert_eq!(imports["kernel32.dll"], set(&["CreateFileW", "ReadFile"]));
+        // The import by ordinal is skipped
+        assert_eq!(imports["libfoo.dll"], set(&["foo_init"]));
+    }
+
+    #[test]
+    fn exports_in_name_pointer_table() {
+        assert_eq!(parse_exports(HEADERS), set(&["app_main", "app_version"]));
+        assert!(parse_exports("There is no export table").is_empty());
+    }
+
+    #[test]
+    fn absent_dll_is_fine_only_from_system() {
+        let symbols = set(&["CreateFileW", "ReadFile"]);
+        assert_eq!(absent_dll_problems("app.exe", "libfoo.dll", &symbols, false, None),
+                   ["libfoo.dll required by \"app.exe\" is not found"]);
+        assert!(absent_dll_problems("app.exe", "kernel32.dll", &symbols, true, None).is_empty());
+        let system_exports = set(&["CreateFileW"]);
+        assert_eq!(absent_dll_problems("app.exe", "kernel32.dll", &symbols, true, Some(&system_exports)),
+                   ["ReadFile required by \"app.exe\" is not exported by kernel32.dll of the system"]);
+    }
+
+    #[test]
+    fn symbols_missing_from_exports() {
+        let symbols = set(&["foo_init", "foo_exit"]);
+        assert_eq!(missing_symbols("app.exe", "libfoo.dll", &symbols, &set(&["foo_init"])),
+                   ["foo_exit required by \"app.exe\" is not exported by libfoo.dll"]);
+        assert!(missing_symbols("app.exe", "libfoo.dll", &symbols, &set(&["foo_exit", "foo_init"])).is_empty());
+    }
+}
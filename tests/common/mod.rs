@@ -0,0 +1,108 @@
+//! Helpers of the integration tests, which run deploy-dll on generated PE files. They need an objdump in PATH that
+//! reads PE files, like binutils on most Linux distributions, and are skipped otherwise.
+#![allow(clippy::needless_return)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SECTION_RVA: u32 = 0x1000;
+
+/// An empty dir for a test
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("deploy-dll-test-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    return dir;
+}
+
+pub fn objdump_reads_pe() -> bool {
+    let Ok(output) = Command::new("objdump").arg("-i").output() else {
+        return false;
+    };
+    return String::from_utf8_lossy(&output.stdout).contains("pei-x86-64");
+}
+
+/// Append `bytes` to the section at `align`, and return its RVA
+fn add(data: &mut Vec<u8>, bytes: &[u8], align: usize) -> u32 {
+    while !data.len().is_multiple_of(align) {
+        data.push(0);
+    }
+    let offset = data.len();
+    data.extend_from_slice(bytes);
+    return SECTION_RVA + offset as u32;
+}
+
+fn put(buffer: &mut [u8], offset: usize, bytes: &[u8]) {
+    buffer[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+/// Write a minimal x86-64 PE file with one section, which has nothing but the import table
+pub fn write_pe(file: &Path, dll: bool, imports: &[(&str, &[&str])]) {
+    let mut data = vec![0u8; 20 * (imports.len() + 1)];
+    for (i, (name, functions)) in imports.iter().enumerate() {
+        let mut thunks = Vec::new();
+        for function in *functions {
+            let hint_name = [&[0u8, 0u8][..], function.as_bytes(), &[0u8]].concat();
+            thunks.extend_from_slice(&(add(&mut data, &hint_name, 2) as u64).to_le_bytes());
+        }
+        thunks.extend_from_slice(&0u64.to_le_bytes());
+        let lookup_table = add(&mut data, &thunks, 8);
+        let address_table = add(&mut data, &thunks, 8);
+        let name = add(&mut data, &[name.as_bytes(), &[0u8]].concat(), 2);
+        put(&mut data, 20 * i, &lookup_table.to_le_bytes());
+        put(&mut data, 20 * i + 12, &name.to_le_bytes());
+        put(&mut data, 20 * i + 16, &address_table.to_le_bytes());
+    }
+    let virtual_size = data.len() as u32;
+    data.resize(data.len().div_ceil(0x200) * 0x200, 0);
+
+    let mut header = vec![0u8; 0x200];
+    put(&mut header, 0, b"MZ");
+    put(&mut header, 0x3c, &0x40u32.to_le_bytes());
+    put(&mut header, 0x40, b"PE\0\0");
+    // COFF header: machine, one section, size of optional header, characteristics
+    put(&mut header, 0x44, &0x8664u16.to_le_bytes());
+    put(&mut header, 0x46, &1u16.to_le_bytes());
+    put(&mut header, 0x54, &240u16.to_le_bytes());
+    put(&mut header, 0x56, &(if dll { 0x2022u16 } else { 0x0022u16 }).to_le_bytes());
+    // PE32+ optional header
+    let optional = 0x58;
+    put(&mut header, optional, &0x20bu16.to_le_bytes());
+    put(&mut header, optional + 8, &(data.len() as u32).to_le_bytes());
+    put(&mut header, optional + 20, &SECTION_RVA.to_le_bytes());
+    put(&mut header, optional + 24, &0x140000000u64.to_le_bytes());
+    put(&mut header, optional + 32, &0x1000u32.to_le_bytes());
+    put(&mut header, optional + 36, &0x200u32.to_le_bytes());
+    put(&mut header, optional + 40, &6u16.to_le_bytes());
+    put(&mut header, optional + 48, &6u16.to_le_bytes());
+    put(&mut header, optional + 56, &(SECTION_RVA + virtual_size.div_ceil(0x1000) * 0x1000).to_le_bytes());
+    put(&mut header, optional + 60, &0x200u32.to_le_bytes());
+    put(&mut header, optional + 68, &3u16.to_le_bytes());
+    put(&mut header, optional + 70, &0x8160u16.to_le_bytes());
+    put(&mut header, optional + 108, &16u32.to_le_bytes());
+    // Import directory
+    put(&mut header, optional + 120, &SECTION_RVA.to_le_bytes());
+    put(&mut header, optional + 124, &(20 * (imports.len() as u32 + 1)).to_le_bytes());
+    // Section header
+    let section = optional + 240;
+    put(&mut header, section, b".rdata\0\0");
+    put(&mut header, section + 8, &virtual_size.to_le_bytes());
+    put(&mut header, section + 12, &SECTION_RVA.to_le_bytes());
+    put(&mut header, section + 16, &(data.len() as u32).to_le_bytes());
+    put(&mut header, section + 20, &0x200u32.to_le_bytes());
+    put(&mut header, section + 36, &0x40000040u32.to_le_bytes());
+
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(file, [header, data].concat()).unwrap();
+}
+
+/// Files in `dir`, relative to it and sorted
+pub fn files_in(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = walkdir::WalkDir::new(dir).into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.path().strip_prefix(dir).unwrap().to_str().unwrap().replace('\\', "/"))
+        .collect();
+    files.sort();
+    return files;
+}
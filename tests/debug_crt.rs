@@ -0,0 +1,33 @@
+//! Deploys generated PE files that link to the debug C/C++ runtime.
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::process::Command;
+use common::{files_in, objdump_reads_pe, test_dir, write_pe};
+
+#[test]
+fn debug_crt_is_not_deployed() {
+    if !objdump_reads_pe() {
+        eprintln!("Skipped because objdump in PATH can't read PE files");
+        return;
+    }
+    let dir = test_dir("debug-crt");
+    write_pe(&dir.join("dist/app.exe"), false, &[("MSVCP140D.dll", &["msvcp"]), ("ucrtbased.dll", &["ucrt"]),
+        ("libaa.dll", &["aa"])]);
+    write_pe(&dir.join("libs/libaa.dll"), true, &[("VCRUNTIME140D.dll", &["vcruntime"])]);
+    // A debug runtime in the search dirs, like the one installed by Visual Studio, is not copied either
+    write_pe(&dir.join("libs/msvcp140d.dll"), true, &[]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_deploy-dll"))
+        .args(["dist/app.exe", "--shallow-search-dir", "libs", "--target-os", "win10-22h2"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "deploy-dll failed: {stderr}");
+    assert!(stderr.contains("debug C/C++ runtime msvcp140d.dll, ucrtbased.dll, vcruntime140d.dll is not deployed"),
+            "{stderr}");
+    assert_eq!(files_in(&dir.join("dist")), [".deploy-dll.json", "app.exe", "libaa.dll"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
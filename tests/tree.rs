@@ -0,0 +1,47 @@
+//! Runs `deploy-dll --tree` on generated PE files.
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+use common::{files_in, objdump_reads_pe, test_dir, write_pe};
+
+/// Run `deploy-dll --tree dist` in `dir`, and return its output without the lines of copied files
+fn deploy_tree(dir: &Path) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_deploy-dll"))
+        .args(["--tree", "dist", "--shallow-search-dir", "libs", "--target-os", "wine"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "deploy-dll failed: {}", String::from_utf8_lossy(&output.stderr));
+    return String::from_utf8(output.stdout).unwrap().lines()
+        .filter(|line| !line.starts_with("Copying"))
+        .map(|line| line.to_string())
+        .collect();
+}
+
+#[test]
+fn deploying_tree_twice_gives_the_same_result() {
+    if !objdump_reads_pe() {
+        eprintln!("Skipped because objdump in PATH can't read PE files");
+        return;
+    }
+    let dir = test_dir("tree-twice");
+    write_pe(&dir.join("dist/bin/app.exe"), false, &[("libaa.dll", &["aa"])]);
+    write_pe(&dir.join("dist/plugins/plug.dll"), true, &[("libaa.dll", &["aa"])]);
+    write_pe(&dir.join("libs/libaa.dll"), true, &[("libbb.dll", &["bb"])]);
+    write_pe(&dir.join("libs/libbb.dll"), true, &[]);
+
+    let first = deploy_tree(&dir);
+    let first_files = files_in(&dir.join("dist"));
+    let second = deploy_tree(&dir);
+    let second_files = files_in(&dir.join("dist"));
+
+    assert_eq!(first, second);
+    assert_eq!(first_files, second_files);
+    assert!(first.contains(&"Dlls shared by all 2 targets:".to_string()), "{first:?}");
+    // The exe loads libaa.dll from its own dir, which the plugin finds as well
+    assert_eq!(first_files, [".deploy-dll.json", "bin/app.exe", "bin/libaa.dll", "bin/libbb.dll", "plugins/plug.dll"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}